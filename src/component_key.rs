@@ -0,0 +1,129 @@
+// Canonical keys for components_to_chars.json
+//
+// A key is the multiset of components of a character, encoded so that any client can
+// rebuild it from the cards a player selected:
+//
+// 1. Each component is one displayable character or one entity reference (&CDP-8B7A;).
+// 2. Radical variants are folded to their canonical form (氵 → 水, ⺅ → 人; see radicals.rs).
+// 3. Components are sorted by Unicode code point, comparing code point by code point
+//    (not UTF-16 code units, which order astral characters like 𠂉 differently).
+// 4. Repeated components are kept (林 → 木|木).
+// 5. Components are joined with KEY_SEPARATOR.
+//
+// 明 → "日|月", 林 → "木|木", 品 → "口|口|口", 敝 → "&CDP-8B7A;|攴", 海 → "每|水"
+//
+// The same rules are written to components_key_spec.json for clients.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::radicals::{fold_component, radical_folding};
+
+/// Separates components in a key; never part of a component or entity reference
+pub const KEY_SEPARATOR: char = '|';
+
+/// Version of the key format, bumped whenever the rules above change
+pub const KEY_FORMAT_VERSION: u32 = 3;
+
+/// Build the key for a list of components (any order, duplicates counted)
+pub fn component_key<S: AsRef<str>>(components: &[S]) -> String {
+    let mut sorted: Vec<_> = components.iter().map(|c| fold_component(c.as_ref())).collect();
+    // str ordering compares UTF-8 bytes, which is the same as comparing code points
+    sorted.sort_unstable();
+    sorted.join(&KEY_SEPARATOR.to_string())
+}
+
+/// Build the key for a component multiset (component → count)
+pub fn key_from_counts(counts: &BTreeMap<String, usize>) -> String {
+    let components: Vec<&str> = counts
+        .iter()
+        .flat_map(|(component, &count)| std::iter::repeat_n(component.as_str(), count))
+        .collect();
+    component_key(&components)
+}
+
+/// Split a key back into its components
+pub fn parse_component_key(key: &str) -> Vec<String> {
+    if key.is_empty() {
+        return Vec::new();
+    }
+    key.split(KEY_SEPARATOR).map(str::to_string).collect()
+}
+
+/// Machine-readable description of the key format, saved next to components_to_chars.json
+#[derive(Debug, Serialize)]
+pub struct ComponentKeySpec {
+    pub version: u32,
+    pub separator: char,
+    pub order: &'static str,
+    pub duplicates: &'static str,
+    pub component: &'static str,
+    pub folding: &'static str,
+    /// Radical variant → the canonical form it is replaced with
+    pub radical_folding: BTreeMap<String, String>,
+    pub examples: BTreeMap<&'static str, String>,
+}
+
+pub fn component_key_spec() -> ComponentKeySpec {
+    let examples = [
+        ("明", vec!["日", "月"]),
+        ("林", vec!["木", "木"]),
+        ("品", vec!["口", "口", "口"]),
+        ("敝", vec!["攵", "&CDP-8B7A;"]),
+        ("海", vec!["氵", "每"]),
+    ]
+    .into_iter()
+    .map(|(character, components)| (character, component_key(&components)))
+    .collect();
+
+    ComponentKeySpec {
+        version: KEY_FORMAT_VERSION,
+        separator: KEY_SEPARATOR,
+        order: "ascending Unicode code point, compared code point by code point",
+        duplicates: "repeated components appear once per occurrence",
+        component: "a single character or a whole entity reference such as &CDP-8B7A;",
+        folding: "each single-character component is replaced by radical_folding[component] if present",
+        radical_folding: radical_folding(),
+        examples,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_is_order_independent_and_keeps_duplicates() {
+        assert_eq!(component_key(&["月", "日"]), "日|月");
+        assert_eq!(component_key(&["木", "木"]), "木|木");
+    }
+
+    #[test]
+    fn test_entities_cannot_collide_with_plain_characters() {
+        // Without a separator both of these used to be "&CDP-8B7A;攵"-style strings that
+        // could not be split back apart reliably
+        let with_entity = component_key(&["攵", "&CDP-8B7A;"]);
+        assert_eq!(parse_component_key(&with_entity), vec!["&CDP-8B7A;", "攴"]);
+        assert_ne!(component_key(&["&", "CDP-8B7A;"]), component_key(&["&CDP-8B7A;"]));
+    }
+
+    #[test]
+    fn test_code_point_order_for_astral_characters() {
+        // U+FF5E sorts before U+20089 by code point, but after it in UTF-16
+        assert_eq!(component_key(&["𠂉", "～"]), "～|𠂉");
+    }
+
+    #[test]
+    fn test_radical_variants_share_a_key() {
+        assert_eq!(component_key(&["氵", "每"]), "每|水");
+        assert_eq!(component_key(&["⺡", "每"]), component_key(&["水", "每"]));
+        assert_eq!(component_key(&["亻", "木"]), component_key(&["人", "木"]));
+    }
+
+    #[test]
+    fn test_key_from_counts_matches_component_key() {
+        let counts = BTreeMap::from([("口".to_string(), 3)]);
+        assert_eq!(key_from_counts(&counts), component_key(&["口", "口", "口"]));
+        assert_eq!(parse_component_key(""), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,231 @@
+// component_roles.rs - Semantic and phonetic parts of HSK characters
+//
+// The character dictionary labels the components of many characters with what they
+// contribute: 氵 in 河 carries the meaning ("water"), 可 carries the sound (kě → hé), and
+// some parts are iconic (a picture of the thing). The labels name components the way the
+// dictionary's etymology sees them, which need not match how IDS splits the character, so
+// each labelled component is looked up in the IDS decomposition tree (radical variants
+// folded, so the dictionary's 水 finds the 氵 of the IDS) to learn where it sits and which
+// cards make it up.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::game_data_builder::{leaves_of, CharacterDecomposition, DecompositionNode};
+use crate::ids::Position;
+use crate::radicals::fold_component;
+
+/// What a component contributes to its character, as labelled by the dictionary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentRole {
+    /// Hints at the meaning (semantic component)
+    Meaning,
+    /// Hints at the pronunciation (phonetic component)
+    Sound,
+    /// Depicts the thing itself
+    Iconic,
+    /// Stands in for a more complex part of the traditional form
+    Simplified,
+    /// Only there to tell the character apart from a similar one
+    Distinguishing,
+    /// Left over from an older form
+    Remnant,
+    /// Any other or missing label
+    Unknown,
+}
+
+impl ComponentRole {
+    /// Parse a dictionary label ("meaning", "sound", ...); unrecognized labels are Unknown
+    pub fn parse(label: &str) -> ComponentRole {
+        match label.trim().to_lowercase().as_str() {
+            "meaning" => ComponentRole::Meaning,
+            "sound" => ComponentRole::Sound,
+            "iconic" => ComponentRole::Iconic,
+            "simplified" => ComponentRole::Simplified,
+            "distinguishing" => ComponentRole::Distinguishing,
+            "remnant" => ComponentRole::Remnant,
+            _ => ComponentRole::Unknown,
+        }
+    }
+}
+
+/// One labelled component from the dictionary
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentAnnotation {
+    pub component: String,
+    pub roles: Vec<ComponentRole>,
+    pub hint: Option<String>,
+}
+
+/// A labelled component merged with the IDS decomposition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotatedComponent {
+    /// The component as the IDS writes it, or as the dictionary does if the IDS lacks it
+    pub component: String,
+    pub roles: Vec<ComponentRole>,
+    /// The dictionary's note, e.g. the meaning or reading the component stands for
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub hint: Option<String>,
+    /// Where the component sits inside its parent in the IDS tree; absent if the IDS does not contain it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+    /// The cards that make up the component; empty if the IDS does not contain it
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub leaves: Vec<String>,
+}
+
+/// The labelled components of one character, saved in component_roles.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentRoles {
+    /// Components that hint at the meaning
+    pub semantic: Vec<String>,
+    /// Components that hint at the sound
+    pub phonetic: Vec<String>,
+    pub components: Vec<AnnotatedComponent>,
+}
+
+/// Depth-first search of the IDS tree for a named node equal to `component` after folding
+fn find_node<'a>(node: &'a DecompositionNode, component: &str) -> Option<&'a DecompositionNode> {
+    node.children.iter().find_map(|child| {
+        let matches = child
+            .component
+            .as_deref()
+            .is_some_and(|name| fold_component(name) == fold_component(component));
+        if matches {
+            Some(child)
+        } else {
+            find_node(child, component)
+        }
+    })
+}
+
+/// Merge the dictionary labels of `character` with its IDS decomposition
+pub fn annotate_character(
+    character: &str,
+    annotations: &[ComponentAnnotation],
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> ComponentRoles {
+    let tree = decompositions.get(character).and_then(|d| d.tree.as_ref());
+    let character_leaves = leaves_of(character, decompositions);
+
+    let components: Vec<AnnotatedComponent> = annotations
+        .iter()
+        .map(|annotation| {
+            let node = tree.and_then(|tree| find_node(tree, &annotation.component));
+            let (component, position, leaves) = match node.and_then(|n| n.component.as_ref().map(|c| (n, c))) {
+                Some((node, glyph)) => {
+                    // A component kept whole by the granularity is a single card
+                    let leaves = if character_leaves.contains(glyph) {
+                        vec![glyph.clone()]
+                    } else {
+                        leaves_of(glyph, decompositions)
+                    };
+                    (glyph.clone(), node.position, leaves)
+                }
+                None => (annotation.component.clone(), None, Vec::new()),
+            };
+            AnnotatedComponent {
+                component,
+                roles: annotation.roles.clone(),
+                hint: annotation.hint.clone(),
+                position,
+                leaves,
+            }
+        })
+        .collect();
+
+    let with_role = |role: ComponentRole| {
+        components
+            .iter()
+            .filter(|c| c.roles.contains(&role))
+            .map(|c| c.component.clone())
+            .collect()
+    };
+
+    ComponentRoles {
+        semantic: with_role(ComponentRole::Meaning),
+        phonetic: with_role(ComponentRole::Sound),
+        components,
+    }
+}
+
+/// Annotate every HSK character the dictionary labels
+pub fn build_component_roles(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    annotations: &HashMap<String, Vec<ComponentAnnotation>>,
+) -> BTreeMap<String, ComponentRoles> {
+    hsk_words
+        .values()
+        .flatten()
+        .flat_map(|word| word.chars())
+        .filter_map(|character| {
+            let character = character.to_string();
+            let labelled = annotations.get(&character).filter(|a| !a.is_empty())?;
+            let roles = annotate_character(&character, labelled, decompositions);
+            Some((character, roles))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data_builder::decompositions_from;
+
+    fn annotation(component: &str, roles: &[&str], hint: Option<&str>) -> ComponentAnnotation {
+        ComponentAnnotation {
+            component: component.to_string(),
+            roles: roles.iter().map(|r| ComponentRole::parse(r)).collect(),
+            hint: hint.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_semantic_and_phonetic_parts_are_located_in_the_ids() {
+        // The dictionary writes 水 where the IDS has 氵
+        let decompositions = decompositions_from(&[("河", "⿰氵可"), ("可", "⿱丁口")]);
+        let annotations = [annotation("水", &["meaning"], Some("water")), annotation("可", &["sound"], None)];
+        let roles = annotate_character("河", &annotations, &decompositions);
+
+        assert_eq!(roles.semantic, vec!["氵"]);
+        assert_eq!(roles.phonetic, vec!["可"]);
+        assert_eq!(roles.components[0].position, Some(Position::Left));
+        assert_eq!(roles.components[0].hint.as_deref(), Some("water"));
+        assert_eq!(roles.components[1].position, Some(Position::Right));
+        assert_eq!(roles.components[1].leaves, vec!["丁", "口"]);
+    }
+
+    #[test]
+    fn test_components_missing_from_the_ids_are_kept_without_position() {
+        let decompositions = decompositions_from(&[("年", "⿱𠂉⿻㐄丨")]);
+        let annotations = [annotation("禾", &["meaning", "iconic"], None), annotation("千", &["Sound"], None)];
+        let roles = annotate_character("年", &annotations, &decompositions);
+
+        assert_eq!(roles.semantic, vec!["禾"]);
+        assert_eq!(roles.phonetic, vec!["千"]);
+        assert_eq!(roles.components[0].roles, vec![ComponentRole::Meaning, ComponentRole::Iconic]);
+        assert!(roles.components.iter().all(|c| c.position.is_none() && c.leaves.is_empty()));
+    }
+
+    #[test]
+    fn test_only_labelled_hsk_characters_are_annotated() {
+        let decompositions = decompositions_from(&[("河", "⿰氵可"), ("明", "⿰日月")]);
+        let hsk_words = HashMap::from([(1, vec!["河".to_string(), "明天".to_string()])]);
+        let annotations = HashMap::from([
+            ("河".to_string(), vec![annotation("氵", &["meaning"], None)]),
+            ("湖".to_string(), vec![annotation("氵", &["meaning"], None)]),
+            ("明".to_string(), Vec::new()),
+        ]);
+        let roles = build_component_roles(&hsk_words, &decompositions, &annotations);
+        assert_eq!(roles.keys().collect::<Vec<_>>(), vec!["河"]);
+    }
+
+    #[test]
+    fn test_role_labels_are_parsed_case_insensitively() {
+        assert_eq!(ComponentRole::parse(" Meaning "), ComponentRole::Meaning);
+        assert_eq!(ComponentRole::parse("SOUND"), ComponentRole::Sound);
+        assert_eq!(ComponentRole::parse("deleted"), ComponentRole::Unknown);
+    }
+}
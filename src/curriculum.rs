@@ -0,0 +1,237 @@
+// curriculum.rs - Learning path ordered by component dependencies
+//
+// A character depends on the HSK characters it is built from: 森 needs 林 and 林 needs 木.
+// Components that are not HSK characters themselves are looked through, so a character
+// built from an unnamed intermediate still depends on the HSK characters inside it.
+//
+// Each character belongs to the lowest HSK level of any word containing it. Levels are
+// taught in order, and within a level characters are ordered topologically (Kahn's
+// algorithm), breaking ties by difficulty score and then code point. Prerequisites from a
+// higher level cannot be honoured and are reported instead; a cycle within a level is
+// broken at its easiest member, and that break is reported as well.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::game_data_builder::{CharacterDecomposition, Difficulty};
+
+/// A character whose prerequisite is only taught at a later HSK level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatePrerequisite {
+    pub character: String,
+    pub level: u8,
+    pub prerequisite: String,
+    pub prerequisite_level: u8,
+}
+
+/// A character taught before some of its same-level prerequisites to break a cycle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CycleBreak {
+    pub character: String,
+    pub level: u8,
+    pub unmet_prerequisites: Vec<String>,
+}
+
+/// The learning path, saved as curriculum.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Curriculum {
+    /// HSK level → characters in teaching order
+    pub levels: BTreeMap<u8, Vec<String>>,
+    /// Character → HSK characters it is built from
+    pub prerequisites: BTreeMap<String, Vec<String>>,
+    pub late_prerequisites: Vec<LatePrerequisite>,
+    pub cycle_breaks: Vec<CycleBreak>,
+}
+
+/// The lowest HSK level of any word containing each character
+pub fn character_levels(hsk_words: &HashMap<u8, Vec<String>>) -> HashMap<String, u8> {
+    let mut levels: HashMap<String, u8> = HashMap::new();
+    for (&level, words) in hsk_words {
+        for character in words.iter().flat_map(|word| word.chars()) {
+            let entry = levels.entry(character.to_string()).or_insert(level);
+            *entry = (*entry).min(level);
+        }
+    }
+    levels
+}
+
+/// The nearest HSK characters inside `character`'s decomposition
+pub fn find_prerequisites(
+    character: &str,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    hsk_characters: &HashMap<String, u8>,
+) -> BTreeSet<String> {
+    let mut prerequisites = BTreeSet::new();
+    let mut visited = HashSet::from([character.to_string()]);
+    let mut pending: Vec<&str> = decompositions
+        .get(character)
+        .map(|d| d.components.iter().map(String::as_str).collect())
+        .unwrap_or_default();
+
+    while let Some(component) = pending.pop() {
+        if !visited.insert(component.to_string()) {
+            continue;
+        }
+        if hsk_characters.contains_key(component) {
+            prerequisites.insert(component.to_string());
+        } else if let Some(decomposition) = decompositions.get(component) {
+            pending.extend(decomposition.components.iter().map(String::as_str));
+        }
+    }
+
+    prerequisites
+}
+
+/// Order the characters of every HSK level so prerequisites come first
+pub fn build_curriculum(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    difficulty: &BTreeMap<String, Difficulty>,
+) -> Curriculum {
+    let levels = character_levels(hsk_words);
+    let mut curriculum = Curriculum::default();
+
+    let mut by_level: BTreeMap<u8, Vec<&String>> = BTreeMap::new();
+    for (character, level) in &levels {
+        by_level.entry(*level).or_default().push(character);
+    }
+
+    // Easier characters first among those that are ready to be taught
+    let sort_key = |character: &str| {
+        let score = difficulty.get(character).map_or(0.0, |d| d.score);
+        (ordered_score(score), character.to_string())
+    };
+
+    for (level, characters) in by_level {
+        // Same-level prerequisites still to be taught, per character
+        let mut waiting: HashMap<&str, BTreeSet<String>> = HashMap::new();
+        for &character in &characters {
+            let prerequisites = find_prerequisites(character, decompositions, &levels);
+            for prerequisite in &prerequisites {
+                let prerequisite_level = levels[prerequisite];
+                if prerequisite_level > level {
+                    curriculum.late_prerequisites.push(LatePrerequisite {
+                        character: character.clone(),
+                        level,
+                        prerequisite: prerequisite.clone(),
+                        prerequisite_level,
+                    });
+                }
+            }
+            if !prerequisites.is_empty() {
+                curriculum
+                    .prerequisites
+                    .insert(character.clone(), prerequisites.iter().cloned().collect());
+            }
+            let same_level = prerequisites.into_iter().filter(|p| levels[p] == level).collect();
+            waiting.insert(character.as_str(), same_level);
+        }
+
+        let mut order = Vec::with_capacity(characters.len());
+        let mut remaining: BTreeSet<_> = characters.iter().map(|c| sort_key(c)).collect();
+        while !remaining.is_empty() {
+            let ready = remaining
+                .iter()
+                .find(|(_, character)| waiting[character.as_str()].is_empty())
+                .cloned();
+            let next = match ready {
+                Some(next) => next,
+                None => {
+                    // Every remaining character waits on another: a cycle
+                    let next = remaining.first().cloned().expect("remaining is not empty");
+                    curriculum.cycle_breaks.push(CycleBreak {
+                        character: next.1.clone(),
+                        level,
+                        unmet_prerequisites: waiting[next.1.as_str()].iter().cloned().collect(),
+                    });
+                    next
+                }
+            };
+
+            remaining.remove(&next);
+            for prerequisites in waiting.values_mut() {
+                prerequisites.remove(&next.1);
+            }
+            order.push(next.1);
+        }
+        curriculum.levels.insert(level, order);
+    }
+
+    curriculum.late_prerequisites.sort_by(|a, b| {
+        (a.level, &a.character, &a.prerequisite).cmp(&(b.level, &b.character, &b.prerequisite))
+    });
+    curriculum
+}
+
+/// Total order on non-negative scores, for use in sorted sets
+fn ordered_score(score: f64) -> u64 {
+    (score.max(0.0) * 1000.0).round() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data_builder::decompositions_from;
+
+    fn words(levels: &[(u8, &[&str])]) -> HashMap<u8, Vec<String>> {
+        levels
+            .iter()
+            .map(|(level, words)| (*level, words.iter().map(|w| w.to_string()).collect()))
+            .collect()
+    }
+
+    fn position(order: &[String], character: &str) -> usize {
+        order.iter().position(|c| c == character).unwrap()
+    }
+
+    #[test]
+    fn test_components_are_taught_before_characters_built_from_them() {
+        let decompositions = decompositions_from(&[("森", "⿱木林"), ("林", "⿰木木"), ("休", "⿰亻木")]);
+        let hsk_words = words(&[(1, &["森林", "木", "休"])]);
+        let curriculum = build_curriculum(&hsk_words, &decompositions, &BTreeMap::new());
+
+        let order = &curriculum.levels[&1];
+        assert!(position(order, "木") < position(order, "林"));
+        assert!(position(order, "林") < position(order, "森"));
+        assert!(position(order, "木") < position(order, "休"));
+        assert_eq!(curriculum.prerequisites["森"], vec!["木", "林"]);
+        assert!(curriculum.cycle_breaks.is_empty());
+    }
+
+    #[test]
+    fn test_unnamed_intermediates_are_looked_through() {
+        // 𦥯 is not an HSK character, but the 爻 inside it is
+        let decompositions = decompositions_from(&[("學", "⿱𦥯子"), ("𦥯", "⿳⿲𦥑爻冖")]);
+        let hsk_words = words(&[(1, &["學", "子"]), (2, &["爻"])]);
+        let prerequisites = find_prerequisites("學", &decompositions, &character_levels(&hsk_words));
+        assert_eq!(prerequisites.into_iter().collect::<Vec<_>>(), vec!["子", "爻"]);
+    }
+
+    #[test]
+    fn test_higher_level_prerequisites_are_reported() {
+        let decompositions = decompositions_from(&[("明", "⿰日月")]);
+        let hsk_words = words(&[(1, &["明", "日"]), (3, &["月"])]);
+        let curriculum = build_curriculum(&hsk_words, &decompositions, &BTreeMap::new());
+
+        assert_eq!(curriculum.late_prerequisites, vec![LatePrerequisite {
+            character: "明".to_string(),
+            level: 1,
+            prerequisite: "月".to_string(),
+            prerequisite_level: 3,
+        }]);
+        assert_eq!(curriculum.levels[&3], vec!["月"]);
+    }
+
+    #[test]
+    fn test_cycles_are_broken_and_reported() {
+        let decompositions = decompositions_from(&[("甲", "⿰乙口"), ("乙", "⿱甲口")]);
+        let hsk_words = words(&[(1, &["甲", "乙"])]);
+        let curriculum = build_curriculum(&hsk_words, &decompositions, &BTreeMap::new());
+
+        assert_eq!(curriculum.levels[&1].len(), 2);
+        assert_eq!(curriculum.cycle_breaks.len(), 1);
+        let broken = &curriculum.cycle_breaks[0];
+        assert_eq!(broken.unmet_prerequisites.len(), 1);
+        assert_ne!(broken.character, broken.unmet_prerequisites[0]);
+    }
+}
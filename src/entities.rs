@@ -0,0 +1,354 @@
+// Entity reference resolution
+//
+// IDS data refers to glyphs without a Unicode code point through entity references
+// such as &CDP-8B7A; or &GT-K00059;. No font can display these, so before game data is
+// built every reference is replaced by something a learner can see:
+//
+// 1. an entry in the hand-maintained mapping table (ids/entity_map.txt)
+// 2. the code point embedded in the reference (&U-i003+5915; is a variant of U+5915),
+//    or the character a JIS X 0208 reference (&I-J90-3022;) stands for
+// 3. a Unicode character whose IDS is identical to the entity's IDS
+// 4. the entity's own decomposition, inlined as a nested sequence
+//
+// Anything left over is reported as unresolved, and the character using it is kept
+// whole rather than decomposed into a part nobody can display. Entities are only ever
+// parts, so they are not characters of the resolved data either.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::ids::Ids;
+
+/// How an entity reference was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionMethod {
+    /// Listed in the mapping table
+    Table,
+    /// The reference embeds a Unicode code point
+    Codepoint,
+    /// A Unicode character has exactly the same IDS
+    SameIds,
+    /// Replaced by its own decomposition
+    Decomposition,
+}
+
+/// What an entity reference was replaced with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityMapping {
+    /// A single character, or an IDS when resolved by decomposition
+    pub resolved: String,
+    pub method: ResolutionMethod,
+}
+
+/// Result of resolving every entity reference used in the IDS data
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EntityReport {
+    pub mappings: BTreeMap<String, EntityMapping>,
+    /// Entity references that could not be resolved → number of IDS entries using them
+    pub unresolved: BTreeMap<String, usize>,
+}
+
+/// Load the entity mapping table
+/// Format: &ENTITY;<tab>REPLACEMENT, where the replacement is a character or an IDS.
+/// Lines starting with '#' are comments. A missing file gives an empty table.
+pub fn load_entity_table(path: &str) -> Result<HashMap<String, String>> {
+    let mut table = HashMap::new();
+    if !Path::new(path).exists() {
+        return Ok(table);
+    }
+
+    let reader = BufReader::new(File::open(path)?);
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let mut parts = line.split('\t');
+        if let (Some(entity), Some(replacement)) = (parts.next(), parts.next()) {
+            table.insert(entity.trim().to_string(), replacement.trim().to_string());
+        }
+    }
+
+    Ok(table)
+}
+
+/// Code point embedded in a reference, for families that name a variant of a Unicode
+/// character: &U-i003+5915;, &U-v001+8FB6;, &A-compU+6708;, &A-IWDSU+7A74;
+pub fn codepoint_in_entity(entity: &str) -> Option<char> {
+    let body = entity.strip_prefix('&')?.strip_suffix(';')?;
+    let (prefix, hex) = body.rsplit_once('+')?;
+    if !prefix.ends_with('U') && !prefix.contains("U-") {
+        return None;
+    }
+    if !(4..=6).contains(&hex.len()) {
+        return None;
+    }
+    let c = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    // Operator variants (&U-i001+2FF1;) are handled by the IDS parser, not here
+    if ('\u{2FF0}'..='\u{2FFF}').contains(&c) {
+        return None;
+    }
+    Some(c)
+}
+
+/// Unicode character for a JIS X 0208:1990 reference (&J90-3022; or &I-J90-3022;)
+/// The hex digits are the row/cell code, which EUC-JP stores with the high bit set
+pub fn jis_x0208_char(entity: &str) -> Option<char> {
+    let body = entity.strip_prefix('&')?.strip_suffix(';')?;
+    let body = body.strip_prefix("I-").unwrap_or(body);
+    let hex = body.strip_prefix("J90-")?;
+    if hex.len() != 4 {
+        return None;
+    }
+    let code = u16::from_str_radix(hex, 16).ok()?;
+    let bytes = [(code >> 8) as u8 | 0x80, (code & 0xFF) as u8 | 0x80];
+    let decoded = encoding_rs::EUC_JP.decode_without_bom_handling_and_without_replacement(&bytes)?;
+    single_char(&decoded)
+}
+
+/// Alternate spellings of the same reference ("&A-CDP-8B7A;" and "&I-CDP-8B7A;" are
+/// "&CDP-8B7A;")
+fn entity_alias(entity: &str) -> Option<String> {
+    entity
+        .strip_prefix("&A-")
+        .or_else(|| entity.strip_prefix("&I-"))
+        .map(|rest| format!("&{}", rest))
+}
+
+struct Resolver<'a> {
+    ids_map: &'a HashMap<String, String>,
+    table: &'a HashMap<String, String>,
+    /// IDS → the Unicode character it describes (smallest code point if several)
+    by_ids: HashMap<&'a str, &'a str>,
+    resolved: HashMap<String, Option<(Ids, ResolutionMethod)>>,
+    stack: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(ids_map: &'a HashMap<String, String>, table: &'a HashMap<String, String>) -> Self {
+        let mut by_ids: HashMap<&str, &str> = HashMap::new();
+        for (character, ids) in ids_map {
+            if character.starts_with('&') {
+                continue;
+            }
+            let entry = by_ids.entry(ids.as_str()).or_insert(character.as_str());
+            if character.as_str() < *entry {
+                *entry = character.as_str();
+            }
+        }
+
+        Resolver {
+            ids_map,
+            table,
+            by_ids,
+            resolved: HashMap::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn resolve(&mut self, entity: &str) -> Option<(Ids, ResolutionMethod)> {
+        if let Some(done) = self.resolved.get(entity) {
+            return done.clone();
+        }
+        // An entity whose decomposition leads back to itself stays as it is
+        if self.stack.iter().any(|e| e == entity) {
+            return None;
+        }
+
+        self.stack.push(entity.to_string());
+        let result = self.resolve_uncached(entity);
+        self.stack.pop();
+
+        self.resolved.insert(entity.to_string(), result.clone());
+        result
+    }
+
+    fn resolve_uncached(&mut self, entity: &str) -> Option<(Ids, ResolutionMethod)> {
+        if let Some(replacement) = self.table.get(entity) {
+            if let Ok(ids) = Ids::parse(replacement) {
+                return Some((self.substitute(&ids), ResolutionMethod::Table));
+            }
+        }
+
+        if let Some(c) = codepoint_in_entity(entity).or_else(|| jis_x0208_char(entity)) {
+            return Some((Ids::Char(c), ResolutionMethod::Codepoint));
+        }
+
+        if let Some(alias) = entity_alias(entity) {
+            if let Some(found) = self.resolve(&alias) {
+                return Some(found);
+            }
+        }
+
+        let ids = self.ids_map.get(entity)?;
+        if let Some(character) = self.by_ids.get(ids.as_str()) {
+            if let Some(c) = single_char(character) {
+                return Some((Ids::Char(c), ResolutionMethod::SameIds));
+            }
+        }
+
+        let tree = Ids::parse(ids).ok()?;
+        Some((self.substitute(&tree), ResolutionMethod::Decomposition))
+    }
+
+    /// Replace every resolvable entity inside a tree
+    fn substitute(&mut self, ids: &Ids) -> Ids {
+        ids.map_entities(&mut |entity| self.resolve(entity).map(|(replacement, _)| replacement))
+    }
+}
+
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    let c = chars.next()?;
+    chars.next().is_none().then_some(c)
+}
+
+/// Rewrite every IDS so that entity references are replaced by displayable characters
+/// or inlined decompositions
+/// Returns the rewritten map and a report of what each reference was resolved to. The map
+/// has no entity keys, and characters whose IDS still uses an unresolved reference are
+/// left out so they stay leaves.
+pub fn resolve_entities(
+    ids_map: &HashMap<String, String>,
+    table: &HashMap<String, String>,
+) -> (HashMap<String, String>, EntityReport) {
+    let mut resolver = Resolver::new(ids_map, table);
+    let mut report = EntityReport::default();
+    let mut resolved_map = HashMap::with_capacity(ids_map.len());
+
+    for (character, ids) in ids_map {
+        let Ok(tree) = Ids::parse(ids) else {
+            // Malformed entries are reported elsewhere; leave them untouched
+            if !character.starts_with('&') {
+                resolved_map.insert(character.clone(), ids.clone());
+            }
+            continue;
+        };
+
+        let rewritten = tree.map_entities(&mut |entity| {
+            let (replacement, method) = resolver.resolve(entity)?;
+            report.mappings.entry(entity.to_string()).or_insert_with(|| EntityMapping {
+                resolved: replacement.to_string(),
+                method,
+            });
+            Some(replacement)
+        });
+
+        // Whatever is still an entity, including inside inlined decompositions
+        let mut unresolved = false;
+        for leaf in rewritten.leaves() {
+            if leaf.starts_with('&') {
+                *report.unresolved.entry(leaf).or_insert(0) += 1;
+                unresolved = true;
+            }
+        }
+
+        if !unresolved && !character.starts_with('&') {
+            resolved_map.insert(character.clone(), rewritten.with_standard_operators().to_string());
+        }
+    }
+
+    (resolved_map, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_from(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_codepoint_in_entity() {
+        assert_eq!(codepoint_in_entity("&U-i003+5915;"), Some('\u{5915}'));
+        assert_eq!(codepoint_in_entity("&A-compU+6708;"), Some('月'));
+        assert_eq!(codepoint_in_entity("&A-IWDSU+7A74;"), Some('\u{7A74}'));
+        assert_eq!(codepoint_in_entity("&U-i001+2FF1;"), None);
+        assert_eq!(codepoint_in_entity("&CDP-8B7A;"), None);
+        assert_eq!(codepoint_in_entity("&GT-K00059;"), None);
+    }
+
+    #[test]
+    fn test_jis_x0208_char() {
+        assert_eq!(jis_x0208_char("&I-J90-3021;"), Some('亜'));
+        assert_eq!(jis_x0208_char("&J90-3022;"), Some('唖'));
+        assert_eq!(jis_x0208_char("&I-J90-7F7F;"), None);
+        assert_eq!(jis_x0208_char("&CDP-3021;"), None);
+
+        let ids_map = map_from(&[("X", "⿰&I-J90-3021;口")]);
+        let (resolved, report) = resolve_entities(&ids_map, &HashMap::new());
+        assert_eq!(resolved["X"], "⿰亜口");
+        assert_eq!(report.mappings["&I-J90-3021;"].method, ResolutionMethod::Codepoint);
+    }
+
+    #[test]
+    fn test_resolution_order() {
+        let ids_map = map_from(&[
+            ("謠", "⿰言&JX2-7461;"),
+            ("X", "⿰&U-i003+5915;&CDP-1111;"),
+            ("Y", "⿱&CDP-2222;&A-CDP-2222;"),
+            ("&CDP-1111;", "⿱日月"),
+            ("明", "⿱日月"),
+            ("&CDP-2222;", "⿰十&CDP-3333;"),
+            ("Z", "⿰木&GT-K00059;"),
+        ]);
+        let table = map_from(&[("&JX2-7461;", "䍃")]);
+
+        let (resolved, report) = resolve_entities(&ids_map, &table);
+
+        assert_eq!(resolved["謠"], "⿰言䍃");
+        assert_eq!(report.mappings["&JX2-7461;"].method, ResolutionMethod::Table);
+
+        assert_eq!(resolved["X"], "⿰\u{5915}明");
+        assert_eq!(report.mappings["&U-i003+5915;"].method, ResolutionMethod::Codepoint);
+        assert_eq!(report.mappings["&CDP-1111;"].method, ResolutionMethod::SameIds);
+
+        // Decomposition is inlined; the unresolvable inner reference is reported and Y,
+        // like Z, stays whole
+        assert_eq!(report.mappings["&CDP-2222;"].resolved, "⿰十&CDP-3333;");
+        assert_eq!(report.mappings["&CDP-2222;"].method, ResolutionMethod::Decomposition);
+        assert_eq!(report.mappings["&A-CDP-2222;"].method, ResolutionMethod::Decomposition);
+        assert!(!resolved.contains_key("Y"));
+        assert!(report.unresolved.contains_key("&CDP-3333;"));
+
+        assert!(!resolved.contains_key("Z"));
+        assert_eq!(report.unresolved.get("&GT-K00059;"), Some(&1));
+    }
+
+    #[test]
+    fn test_resolved_map_has_no_entities() {
+        let ids_map = map_from(&[
+            ("明", "⿱日月"),
+            ("&CDP-8CCB;", "⿱日月"),
+            ("X", "⿰木&CDP-8CCB;"),
+            ("W", "&U-i001+2FF1;木&U-i003+5915;"),
+            ("Z", "⿰木&GT-K00059;"),
+        ]);
+
+        let (resolved, _) = resolve_entities(&ids_map, &HashMap::new());
+        assert_eq!(resolved["X"], "⿰木明");
+        assert_eq!(resolved["W"], "⿱木\u{5915}");
+        let mut keys: Vec<&String> = resolved.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["W", "X", "明"]);
+    }
+
+    #[test]
+    fn test_cyclic_entities_stay_unresolved() {
+        let ids_map = map_from(&[("&CDP-1;", "⿰&CDP-2;口"), ("&CDP-2;", "⿱&CDP-1;木"), ("A", "⿰&CDP-1;日")]);
+
+        let (resolved, report) = resolve_entities(&ids_map, &HashMap::new());
+        assert!(!resolved.contains_key("A"));
+        assert!(!report.unresolved.is_empty());
+        assert!(report.unresolved.keys().all(|entity| entity.starts_with("&CDP-")));
+    }
+}
@@ -0,0 +1,148 @@
+// frequency.rs - How common each HSK word and character is, for weighted sampling
+//
+// The dictionaries rank words and characters by how often they appear in a film subtitle
+// corpus and a book corpus (1 = most common). The two ranks are averaged; an entry with
+// only one rank uses it. Within each HSK level entries are ordered most common first, and
+// each gets a sampling weight proportional to 1 / √rank, so 的 comes up more often than a
+// rare character of the same level without drowning it out (plain 1 / rank would). An
+// entry with no rank at all weighs as much as the rarest ranked entry of its level.
+//
+// Weights are normalized to sum to 1 within a level, so a client can draw directly.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Corpus ranks of one word or character; lower is more common
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CorpusRanks {
+    pub movie: Option<i64>,
+    pub book: Option<i64>,
+}
+
+impl CorpusRanks {
+    /// Keep the more common rank of each corpus, for entries sharing a form
+    pub fn merge(&mut self, other: CorpusRanks) {
+        let best = |a: Option<i64>, b: Option<i64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.movie = best(self.movie, other.movie);
+        self.book = best(self.book, other.book);
+    }
+
+    /// Mean of the known ranks; None if neither corpus ranks the entry
+    pub fn combined(&self) -> Option<f64> {
+        let known: Vec<f64> = [self.movie, self.book]
+            .into_iter()
+            .flatten()
+            .filter(|&rank| rank > 0)
+            .map(|rank| rank as f64)
+            .collect();
+        (!known.is_empty()).then(|| known.iter().sum::<f64>() / known.len() as f64)
+    }
+}
+
+/// A word or character with its frequency, saved in word_frequency.json and char_frequency.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyEntry {
+    pub form: String,
+    /// Position within the level, 1 = most common
+    pub rank: usize,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub movie_rank: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub book_rank: Option<i64>,
+    /// Share of draws within the level
+    pub weight: f64,
+}
+
+/// Order each level's entries most common first and weight them for sampling
+pub fn build_frequency_levels(
+    levels: &BTreeMap<u8, Vec<String>>,
+    ranks: &HashMap<String, CorpusRanks>,
+) -> BTreeMap<u8, Vec<FrequencyEntry>> {
+    levels
+        .iter()
+        .map(|(&level, forms)| {
+            let mut ranked: Vec<(&String, CorpusRanks, Option<f64>)> = forms
+                .iter()
+                .map(|form| {
+                    let corpus = ranks.get(form).copied().unwrap_or_default();
+                    (form, corpus, corpus.combined())
+                })
+                .collect();
+            // Unranked entries go last, in code point order
+            ranked.sort_by(|a, b| match (a.2, b.2) {
+                (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.0.cmp(b.0)),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.0.cmp(b.0),
+            });
+
+            let raw = |rank: f64| 1.0 / rank.max(1.0).sqrt();
+            let floor = ranked.iter().filter_map(|entry| entry.2).map(raw).fold(1.0, f64::min);
+            let weights: Vec<f64> = ranked.iter().map(|entry| entry.2.map_or(floor, raw)).collect();
+            let total: f64 = weights.iter().sum();
+
+            let entries = ranked
+                .into_iter()
+                .zip(weights)
+                .enumerate()
+                .map(|(index, ((form, corpus, _), weight))| FrequencyEntry {
+                    form: form.clone(),
+                    rank: index + 1,
+                    movie_rank: corpus.movie,
+                    book_rank: corpus.book,
+                    weight: weight / total,
+                })
+                .collect();
+            (level, entries)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranks(movie: Option<i64>, book: Option<i64>) -> CorpusRanks {
+        CorpusRanks { movie, book }
+    }
+
+    #[test]
+    fn test_entries_are_ordered_and_weighted_by_rank() {
+        let levels = BTreeMap::from([(1, vec!["们".to_string(), "的".to_string(), "猫".to_string(), "一".to_string()])]);
+        let corpus = HashMap::from([
+            ("的".to_string(), ranks(Some(1), Some(1))),
+            ("一".to_string(), ranks(Some(6), Some(2))),
+            ("们".to_string(), ranks(None, Some(16))),
+        ]);
+        let entries = &build_frequency_levels(&levels, &corpus)[&1];
+
+        let forms: Vec<&str> = entries.iter().map(|e| e.form.as_str()).collect();
+        assert_eq!(forms, vec!["的", "一", "们", "猫"]);
+        assert_eq!(entries[1].rank, 2);
+        assert_eq!(entries[2].movie_rank, None);
+
+        // 1, 1/2, 1/4, and the unranked 猫 as much as 们
+        let total = 1.0 + 0.5 + 0.25 + 0.25;
+        assert!((entries[0].weight - 1.0 / total).abs() < 1e-9);
+        assert!((entries[3].weight - entries[2].weight).abs() < 1e-9);
+        assert!((entries.iter().map(|e| e.weight).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_merged_ranks_keep_the_most_common() {
+        let mut merged = ranks(Some(40), None);
+        merged.merge(ranks(Some(10), Some(30)));
+        assert_eq!(merged, ranks(Some(10), Some(30)));
+        assert_eq!(merged.combined(), Some(20.0));
+        assert_eq!(CorpusRanks::default().combined(), None);
+
+        // A level without any ranks is sampled uniformly
+        let levels = BTreeMap::from([(2, vec!["甲".to_string(), "乙".to_string()])]);
+        let entries = &build_frequency_levels(&levels, &HashMap::new())[&2];
+        assert_eq!(entries[0].form, "乙");
+        assert!(entries.iter().all(|e| (e.weight - 0.5).abs() < 1e-9));
+    }
+}
@@ -1,14 +1,19 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
-
-/// IDS operators that describe character composition (we filter these out)
-/// Unicode range U+2FF0 to U+2FFF (all 16 IDS operators)
-const IDS_OPERATORS: &[char] = &[
-    '⿰', '⿱', '⿲', '⿳', '⿴', '⿵', '⿶', '⿷', '⿸', '⿹', '⿺', '⿻', '⿼', '⿽', '⿾', '⿿',
-];
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use crate::component_key::{component_key_spec, key_from_counts};
+use crate::component_roles::{build_component_roles, ComponentAnnotation, ComponentRoles};
+use crate::curriculum::{build_curriculum, Curriculum};
+use crate::entities::{jis_x0208_char, resolve_entities, EntityReport};
+use crate::phonetic_series::{build_phonetic_series, PhoneticSeries};
+use crate::radicals::fold_component;
+use crate::solver::{verify_hsk_characters, SolvabilityReport};
+use crate::ids::{extended_idc_operator, Ids, IdsOperator, Position};
 
 /// Character to its IDS decomposition mapping
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,12 +21,41 @@ pub struct CharacterDecomposition {
     pub character: String,
     pub ids: String,
     pub components: Vec<String>,
+    /// Leaf components the player builds the character from, at the chosen granularity
+    #[serde(default)]
+    pub leaves: Vec<String>,
+    /// Full recursive decomposition (absent if the IDS is malformed)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub tree: Option<DecompositionNode>,
+}
+
+/// One node of a recursive decomposition tree
+///
+/// 森 (⿱木林) becomes a node for 森 with operator ⿱ whose children are the leaf 木 (top)
+/// and the node for 林 (bottom), which in turn has operator ⿰ and two 木 leaves.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecompositionNode {
+    /// Character or entity this node stands for; absent for a nested sequence that has
+    /// no name of its own (the ⿰木木 in ⿱木⿰木木)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub component: Option<String>,
+    /// IDS operator joining the children; absent for leaves
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub operator: Option<char>,
+    /// Where this node sits inside its parent; absent for the root
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<Position>,
+    /// Number of leaves below this node (1 for a leaf), repeated parts included
+    pub leaf_count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub children: Vec<DecompositionNode>,
 }
 
 /// Mapping from a set of components to characters that can be formed
+#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ComponentsToCharacters {
-    /// Sorted component string as key (e.g., "日月" for 明)
+    /// Canonical component key (see component_key.rs), e.g. "日|月" for 明, "木|木" for 林
     pub components_key: String,
     /// Characters that can be formed from these components
     pub characters: Vec<String>,
@@ -32,20 +66,200 @@ pub struct ComponentsToCharacters {
 pub struct GameData {
     /// Character → decomposition mapping
     pub char_to_decomposition: HashMap<String, CharacterDecomposition>,
-    /// Components → characters mapping (sorted components as key)
+    /// Components → characters mapping, keyed by canonical component keys (see component_key.rs)
     pub components_to_chars: HashMap<String, Vec<String>>,
     /// Set of all leaf components used in HSK words
     pub allowed_components: HashSet<String>,
+    /// Leaf component → max copies needed to build any single HSK character
+    pub allowed_component_counts: HashMap<String, usize>,
+    /// Folded allowed component → the radical variant glyphs HSK characters use for it
+    pub component_forms: BTreeMap<String, BTreeSet<String>>,
     /// HSK level → words mapping
     pub hsk_words: HashMap<u8, Vec<String>>,
+    /// Cycles found in the IDS data (members are treated as leaves when reached again)
+    pub ids_cycles: Vec<Vec<String>>,
+    /// IDS entries that do not parse as a well-formed sequence
+    pub malformed_ids: Vec<MalformedIds>,
+    /// Every description of characters that have more than one
+    pub ids_alternatives: BTreeMap<String, Vec<IdsAlternative>>,
+    /// What each loaded IDS file contributed, in load order
+    pub ids_source_summary: Vec<IdsSourceSummary>,
+    /// What each entity reference was replaced with, and which could not be resolved
+    pub entity_report: EntityReport,
+    /// Which HSK characters can be built with pairwise/triplet combinations
+    pub solvability: SolvabilityReport,
+    /// HSK character → how hard it is to build
+    pub difficulty: BTreeMap<String, Difficulty>,
+    /// Per-level teaching order that introduces components before the characters using them
+    pub curriculum: Curriculum,
+    /// HSK character → its semantic and phonetic components
+    pub component_roles: BTreeMap<String, ComponentRoles>,
+    /// Phonetic component → HSK characters using it, with their readings
+    pub phonetic_series: BTreeMap<String, PhoneticSeries>,
 }
 
-/// Parse IDS file and return character → IDS mapping
+/// Per-character facts taken from the character dictionary
+#[derive(Debug, Default)]
+pub struct CharacterFacts {
+    pub stroke_counts: HashMap<String, u32>,
+    /// Dictionary labels of each character's components (meaning, sound, ...)
+    pub component_annotations: HashMap<String, Vec<ComponentAnnotation>>,
+    /// Readings, most frequent first
+    pub pinyin: HashMap<String, Vec<String>>,
+}
+
+/// Phonetic series with fewer members are not written to phonetic_series.json
+pub const MIN_SERIES_MEMBERS: usize = 2;
+
+/// How hard a character is to build, saved in difficulty.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    /// Levels of named components below the character (明 → 1, 森 → 2 through 林)
+    pub depth: usize,
+    /// Leaf components the player starts from, duplicates counted
+    pub leaf_count: usize,
+    /// Combinations in the shortest build sequence (absent if it cannot be built)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build_steps: Option<usize>,
+    /// Other characters sharing the component set of the character or one of its
+    /// intermediates, i.e. wrong answers a correct combination could also produce
+    pub ambiguity: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stroke_count: Option<u32>,
+    /// Weighted sum of the above (see DIFFICULTY_WEIGHTS); higher is harder
+    pub score: f64,
+}
+
+/// Weights of depth, leaf count, build steps, ambiguity and stroke count in Difficulty::score
+pub const DIFFICULTY_WEIGHTS: [f64; 5] = [1.0, 0.5, 1.0, 0.5, 0.1];
+
+/// An IDS entry rejected by the strict parser
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MalformedIds {
+    pub character: String,
+    pub ids: String,
+    pub error: String,
+}
+
+/// One IDS description of a character, as found in a source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdsAlternative {
+    /// The description, without any trailing region tag
+    pub ids: String,
+    /// Name of the IDS file it came from (e.g. "IDS-UCS-Basic")
+    pub source: String,
+    /// Region letters from a trailing [GTJK…] tag; empty means it applies everywhere
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub regions: String,
+    /// An @apparent= description of the visible shape rather than the etymological one
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub apparent: bool,
+}
+
+/// Character → every IDS description found for it, in load order
+pub type IdsAlternatives = HashMap<String, Vec<IdsAlternative>>;
+
+/// Which of several IDS descriptions the game uses
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdsPolicy {
+    /// Region letters in order of preference (T = Taiwan, H = Hong Kong, G = PRC, J, K, V…).
+    /// A description tagged with an earlier region wins; untagged descriptions come next,
+    /// then descriptions tagged only for other regions
+    pub preferred_regions: Vec<char>,
+    /// Also consider @apparent= descriptions, preferring them over etymological ones
+    pub use_apparent: bool,
+}
+
+impl Default for IdsPolicy {
+    /// Traditional glyph shapes, matching the default `--script trad` word forms
+    fn default() -> Self {
+        IdsPolicy {
+            preferred_regions: vec!['T', 'H'],
+            use_apparent: false,
+        }
+    }
+}
+
+impl IdsPolicy {
+    fn rank(&self, alternative: &IdsAlternative) -> (usize, bool) {
+        let region_rank = if alternative.regions.is_empty() {
+            self.preferred_regions.len()
+        } else {
+            self.preferred_regions
+                .iter()
+                .position(|&region| alternative.regions.contains(region))
+                .unwrap_or(self.preferred_regions.len() + 1)
+        };
+        (region_rank, alternative.apparent != self.use_apparent)
+    }
+
+    /// Pick the description to use, or None if the policy rules all of them out
+    pub fn select<'a>(&self, alternatives: &'a [IdsAlternative]) -> Option<&'a IdsAlternative> {
+        alternatives
+            .iter()
+            .filter(|alternative| self.use_apparent || !alternative.apparent)
+            // min_by_key keeps the first of equal ranks, so load order breaks ties
+            .min_by_key(|alternative| self.rank(alternative))
+    }
+}
+
+/// Split a trailing region tag off an IDS ("⿰氵每[GTKV]" → ("⿰氵每", "GTKV"))
+fn split_region_tag(ids: &str) -> (&str, &str) {
+    if let Some(body) = ids.strip_suffix(']') {
+        if let Some(open) = body.rfind('[') {
+            let regions = &body[open + 1..];
+            if !regions.is_empty() && regions.chars().all(|c| c.is_ascii_uppercase()) {
+                return (&body[..open], regions);
+            }
+        }
+    }
+    (ids, "")
+}
+
+/// Why a line of an IDS file was not used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DropReason {
+    /// The line has no description at all
+    EmptyIds,
+    /// The only description is a "?" placeholder
+    Placeholder,
+    /// A JIS X 0208 code with no Unicode equivalent
+    UnmappedJis,
+}
+
+/// A line of an IDS file that was skipped
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DroppedIdsEntry {
+    pub character: String,
+    pub reason: DropReason,
+}
+
+/// The descriptions loaded from one IDS file
+#[derive(Debug, Clone)]
+pub struct IdsSource {
+    pub path: String,
+    pub entries: IdsAlternatives,
+    /// Characters the file describes only as themselves (U+91D1 金 金)
+    pub atomic: HashSet<String>,
+    pub dropped: Vec<DroppedIdsEntry>,
+}
+
+/// Parse IDS file and return character → IDS descriptions
 /// Handles both Unicode format (U+XXXX) and entity reference format (CDP-XXXX, J90-XXXX, etc.)
-pub fn parse_ids_file(path: &str) -> Result<HashMap<String, String>> {
+/// Every extra tab-separated column is kept as an alternative description.
+/// JIS X 0208 entries (&I-J90-3022;) are stored under the Unicode character they stand
+/// for, so they become alternatives of the UCS descriptions rather than separate glyphs.
+pub fn parse_ids_file(path: &str) -> Result<IdsSource> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
-    let mut ids_map = HashMap::new();
+    let mut ids_map: IdsAlternatives = HashMap::new();
+    let mut atomic = HashSet::new();
+    let mut dropped = Vec::new();
+    let source = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
 
     for line in reader.lines() {
         let line = line?;
@@ -55,40 +269,138 @@ pub fn parse_ids_file(path: &str) -> Result<HashMap<String, String>> {
             continue;
         }
 
-        // Format: U+XXXX<tab>CHAR<tab>IDS or ENTITY<tab>&ENTITY;<tab>IDS
+        // Format: U+XXXX<tab>CHAR<tab>IDS[<tab>IDS…] or ENTITY<tab>&ENTITY;<tab>IDS[<tab>IDS…]
         let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() >= 3 {
-            let character = parts[1].to_string();
-            let ids = parts[2].to_string();
+        if parts.len() < 3 {
+            continue;
+        }
+
+        let character = match jis_x0208_char(parts[1]) {
+            Some(c) => c.to_string(),
+            None if parts[1].contains("J90-") => {
+                dropped.push(DroppedIdsEntry {
+                    character: parts[1].to_string(),
+                    reason: DropReason::UnmappedJis,
+                });
+                continue;
+            }
+            None => parts[1].to_string(),
+        };
+
+        let mut usable = false;
+        let mut placeholder = false;
+        for column in &parts[2..] {
+            let column = column.trim();
+            let (column, apparent) = match column.strip_prefix("@apparent=") {
+                Some(rest) => (rest, true),
+                None => (column, false),
+            };
+            let (ids, regions) = split_region_tag(column);
+
+            // Placeholders like "?" and empty columns carry no description
+            if ids == "?" {
+                placeholder = true;
+                continue;
+            }
+            if ids.is_empty() {
+                continue;
+            }
+            usable = true;
 
             // Only add if IDS is different from the character itself (has decomposition)
-            if ids != character {
-                ids_map.insert(character, ids);
+            if ids == character || ids == parts[1] {
+                atomic.insert(character.clone());
+                continue;
+            }
+
+            let alternatives = ids_map.entry(character.clone()).or_default();
+            let alternative = IdsAlternative {
+                ids: ids.to_string(),
+                source: source.clone(),
+                regions: regions.to_string(),
+                apparent,
+            };
+            if !alternatives.contains(&alternative) {
+                alternatives.push(alternative);
             }
         }
+
+        if !usable {
+            dropped.push(DroppedIdsEntry {
+                character: parts[1].to_string(),
+                reason: if placeholder { DropReason::Placeholder } else { DropReason::EmptyIds },
+            });
+        }
     }
 
-    Ok(ids_map)
+    // A character with a real description in the same file is not atomic
+    atomic.retain(|character| !ids_map.contains_key(character));
+
+    Ok(IdsSource {
+        path: path.to_string(),
+        entries: ids_map,
+        atomic,
+        dropped,
+    })
 }
 
-/// Load all IDS files and merge them
-/// Includes entity reference files (CDP) to resolve entity references
-/// NOTE: JIS file removed because it contains non-standard character references
-pub fn load_all_ids() -> Result<HashMap<String, String>> {
-    let mut combined = HashMap::new();
-
-    let ids_files = vec![
-        "ids/IDS-UCS-Basic.txt",
-        "ids/IDS-UCS-Ext-A.txt",
-        "ids/IDS-CDP.txt",           // CDP entity references
-        // "ids/IDS-JIS-X0208-1990.txt", // REMOVED: Contains non-standard references like &I-J90-3065;
-    ];
-
-    for file_path in ids_files {
+/// What one IDS source contributed to decomposing HSK characters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdsSourceSummary {
+    pub source: String,
+    /// Characters with at least one description in this file
+    pub entries: usize,
+    /// HSK leaf components that had no description before this source was added and
+    /// can now be decomposed further
+    pub resolved_leaves: Vec<String>,
+    /// Distinct HSK leaf components once this source is included
+    pub leaves_after: usize,
+    /// Lines of the file that were skipped, and why
+    pub dropped: Vec<DroppedIdsEntry>,
+}
+
+/// An IDS file to load, and whether it must exist
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdsFile {
+    pub path: String,
+    pub required: bool,
+}
+
+/// Load each IDS file separately, in order
+/// A missing required file is an error; any other missing file is skipped with a note, so
+/// the default list can name every CHISE UCS file even if only some have been downloaded
+/// into ids/
+pub fn load_ids_sources(ids_files: &[IdsFile]) -> Result<Vec<IdsSource>> {
+    let mut sources = Vec::new();
+
+    for IdsFile { path: file_path, required } in ids_files {
+        if !Path::new(file_path).exists() {
+            if *required {
+                anyhow::bail!("IDS file {} not found", file_path);
+            }
+            println!("  ⏭️  Skipping {} (not found)", file_path);
+            continue;
+        }
         match parse_ids_file(file_path) {
-            Ok(ids_map) => {
-                println!("  ✅ Loaded {} from {}", ids_map.len(), file_path);
-                combined.extend(ids_map);
+            Ok(source) => {
+                println!("  ✅ Loaded {} from {}", source.entries.len(), file_path);
+                if !source.dropped.is_empty() {
+                    let mut by_reason: BTreeMap<DropReason, usize> = BTreeMap::new();
+                    for entry in &source.dropped {
+                        *by_reason.entry(entry.reason).or_insert(0) += 1;
+                    }
+                    let reasons: Vec<String> = by_reason
+                        .iter()
+                        .map(|(reason, count)| format!("{} {:?}", count, reason))
+                        .collect();
+                    println!(
+                        "  ⚠️  Dropped {} entries from {} ({})",
+                        source.dropped.len(),
+                        file_path,
+                        reasons.join(", ")
+                    );
+                }
+                sources.push(source);
             }
             Err(e) => {
                 eprintln!("  ⚠️  Warning: Could not load {}: {}", file_path, e);
@@ -96,40 +408,132 @@ pub fn load_all_ids() -> Result<HashMap<String, String>> {
         }
     }
 
+    Ok(sources)
+}
+
+/// Merge sources in order
+/// Descriptions from later files are added after those from earlier files rather than
+/// replacing them; use select_ids to pick one per character. A character an earlier file
+/// describes only as itself stays atomic: the JIS file splits 金 and 竹 into glyph
+/// fragments with no Unicode equivalent, which would become unbuildable cards.
+pub fn merge_ids_sources(sources: &[IdsSource]) -> IdsAlternatives {
+    let mut combined: IdsAlternatives = HashMap::new();
+    let mut atomic: HashSet<&String> = HashSet::new();
+
+    for source in sources {
+        for (character, alternatives) in &source.entries {
+            if atomic.contains(character) {
+                continue;
+            }
+            let existing = combined.entry(character.clone()).or_default();
+            for alternative in alternatives {
+                // The same description from another file adds nothing
+                let duplicate = existing
+                    .iter()
+                    .any(|e| e.ids == alternative.ids && e.apparent == alternative.apparent);
+                if !duplicate {
+                    existing.push(alternative.clone());
+                }
+            }
+        }
+        // Unless an earlier file already decomposes it
+        atomic.extend(source.atomic.iter().filter(|character| !combined.contains_key(*character)));
+    }
+
+    combined
+}
+
+/// Load all IDS files and merge them
+/// Includes entity reference files (CDP) to resolve entity references.
+pub fn load_all_ids(ids_files: &[IdsFile]) -> Result<IdsAlternatives> {
+    let combined = merge_ids_sources(&load_ids_sources(ids_files)?);
+
+    let with_alternatives = combined.values().filter(|alternatives| alternatives.len() > 1).count();
     println!("  📊 Total unique IDS entries: {}", combined.len());
+    println!("  📊 Characters with alternative descriptions: {}", with_alternatives);
     Ok(combined)
 }
 
+/// Work out how many HSK leaf components each source resolves
+/// Sources are added one at a time in load order; a leaf counts as resolved by the first
+/// source after which it is broken down further. Each step resolves entity references and
+/// decomposes with `granularity`, exactly as the build does, so the last summary's leaves
+/// are the leaves of the game data.
+pub fn summarize_ids_sources(
+    sources: &[IdsSource],
+    hsk_words: &HashMap<u8, Vec<String>>,
+    policy: &IdsPolicy,
+    entity_table: &HashMap<String, String>,
+    granularity: &Granularity,
+) -> Vec<IdsSourceSummary> {
+    let hsk_chars: BTreeSet<String> = hsk_words
+        .values()
+        .flatten()
+        .flat_map(|word| word.chars().map(|c| c.to_string()))
+        .collect();
+
+    // With no IDS data at all, every HSK character is its own leaf
+    let mut previous_leaves = hsk_chars.clone();
+    let mut summaries = Vec::new();
+
+    for i in 0..sources.len() {
+        let ids_map = select_ids(&merge_ids_sources(&sources[..=i]), policy);
+        let (ids_map, _) = resolve_entities(&ids_map, entity_table);
+
+        let leaves: BTreeSet<String> = hsk_chars
+            .iter()
+            .flat_map(|character| decompose_to_leaf_counts(character, &ids_map, granularity).into_keys())
+            .collect();
+
+        let resolved_leaves: Vec<String> = previous_leaves
+            .iter()
+            .filter(|leaf| !leaves.contains(*leaf))
+            .cloned()
+            .collect();
+
+        summaries.push(IdsSourceSummary {
+            source: sources[i].path.clone(),
+            entries: sources[i].entries.len(),
+            resolved_leaves,
+            leaves_after: leaves.len(),
+            dropped: sources[i].dropped.clone(),
+        });
+        previous_leaves = leaves;
+    }
+
+    summaries
+}
+
+/// Pick one IDS per character according to the policy
+pub fn select_ids(alternatives: &IdsAlternatives, policy: &IdsPolicy) -> HashMap<String, String> {
+    alternatives
+        .iter()
+        .filter_map(|(character, alternatives)| {
+            policy
+                .select(alternatives)
+                .map(|alternative| (character.clone(), alternative.ids.clone()))
+        })
+        .collect()
+}
+
 /// Check if an entity reference is an extended IDC operator (non-standard combining character)
 /// These should be filtered out as they're operators, not actual components
 /// Pattern: &U-i###+ followed by a 2FF hex code (IDS operators range)
+///
+/// BUT: &U-i001+20541; is NOT an operator - it's a variant of U+20541
 fn is_extended_idc(entity: &str) -> bool {
-    // Extended IDC entity references follow the pattern &U-i###+2FFx; where x is 0-F
-    // These are IDS operators (U+2FF0 to U+2FFF range), not components
-    // Examples: &U-i001+2FF1;, &U-i002+2FF1;, &U-i001+2FFB;
-    //
-    // BUT: &U-i001+20541; is NOT an operator - it's a variant of U+20541
-    // Only filter if the code point is in the 2FF0-2FFF range (IDS operators)
-    if !entity.starts_with("&U-i") {
-        return false;
-    }
-
-    // Extract the hex code after the '+'
-    if let Some(plus_idx) = entity.find('+') {
-        let after_plus = &entity[plus_idx + 1..];
-        // Check if it starts with "2FF" (IDS operator range)
-        if let Some(semicolon_idx) = after_plus.find(';') {
-            let hex_code = &after_plus[..semicolon_idx];
-            return hex_code.starts_with("2FF");
-        }
-    }
-
-    false
+    extended_idc_operator(entity).is_some()
 }
 
 /// Extract components from IDS string (filtering out operators and extended IDCs)
 /// Properly handles entity references like &CDP-8B7A; as single components
-fn extract_components_from_ids(ids: &str) -> Vec<String> {
+/// Well-formed sequences go through the strict parser; anything else falls back to a
+/// flat scan so malformed entries still yield their parts
+pub fn extract_components_from_ids(ids: &str) -> Vec<String> {
+    if let Ok(tree) = Ids::parse(ids) {
+        return tree.leaves();
+    }
+
     let mut components = Vec::new();
     let mut current = String::new();
     let mut in_entity = false;
@@ -153,7 +557,7 @@ fn extract_components_from_ids(ids: &str) -> Vec<String> {
         } else if in_entity {
             // Inside entity reference
             current.push(c);
-        } else if !IDS_OPERATORS.contains(&c) {
+        } else if IdsOperator::from_char(c).is_none() {
             // Regular character (not an operator)
             components.push(c.to_string());
         }
@@ -168,51 +572,250 @@ fn extract_components_from_ids(ids: &str) -> Vec<String> {
     components
 }
 
+/// Where decomposition stops: below the character being decomposed, a component in
+/// `stops` (Kangxi radicals, HSK characters, glossed characters, …) or one `max_depth`
+/// levels down is kept as a leaf instead of being broken into meaningless fragments.
+/// The default goes all the way down; the character itself is always decomposed, so
+/// `max_depth` is at least 1.
+#[derive(Debug, Clone, Default)]
+pub struct Granularity {
+    pub stops: HashSet<String>,
+    pub max_depth: Option<NonZeroUsize>,
+}
+
+impl Granularity {
+    /// Whether a component `depth` levels below the character being decomposed is a leaf
+    pub fn stops_at(&self, component: &str, depth: usize) -> bool {
+        depth > 0
+            && (self.stops.contains(component) || self.max_depth.is_some_and(|max| depth >= max.get()))
+    }
+}
+
+/// Multiset of leaf components: leaf → number of times it occurs
+/// (e.g. 品 → {口: 3}). Ordered so that keys built from it are deterministic.
+pub type LeafCounts = BTreeMap<String, usize>;
+
 /// Recursively decompose a character to its leaf components
+/// Repeated parts are preserved (林 → [木, 木]), matching the web app's decomposeToLeaves.
+/// `path` holds the characters currently being expanded; a component that is already on
+/// the path is a genuine cycle in the IDS data and is treated as a leaf. Siblings do not
+/// share this state, so the second 口 in 品 decomposes exactly like the first.
+/// Components where `granularity` stops are leaves too.
 pub fn decompose_to_leaves(
     character: &str,
     ids_map: &HashMap<String, String>,
-    visited: &mut HashSet<String>,
-) -> HashSet<String> {
-    let mut leaves = HashSet::new();
-
-    // Prevent infinite recursion
-    if visited.contains(character) {
-        return leaves;
+    granularity: &Granularity,
+    path: &mut Vec<String>,
+) -> Vec<String> {
+    // Prevent infinite recursion on cyclic IDS entries
+    if path.iter().any(|ancestor| ancestor == character) || granularity.stops_at(character, path.len()) {
+        return vec![character.to_string()];
     }
-    visited.insert(character.to_string());
 
     // If no IDS entry, this is a leaf component
-    if let Some(ids) = ids_map.get(character) {
-        let components = extract_components_from_ids(ids);
+    let Some(ids) = ids_map.get(character) else {
+        return vec![character.to_string()];
+    };
+
+    path.push(character.to_string());
+    let mut leaves = Vec::new();
+    for component in extract_components_from_ids(ids) {
+        // Recursively decompose each component
+        leaves.extend(decompose_to_leaves(&component, ids_map, granularity, path));
+    }
+    path.pop();
 
-        for component in components {
-            // Recursively decompose each component
-            let sub_leaves = decompose_to_leaves(&component, ids_map, visited);
-            if sub_leaves.is_empty() {
-                // This component is a leaf
-                leaves.insert(component);
-            } else {
-                // Add all sub-leaves
-                leaves.extend(sub_leaves);
-            }
-        }
-    } else {
-        // No decomposition available, this is a leaf
-        leaves.insert(character.to_string());
+    // An IDS made only of operators gives us nothing to build from
+    if leaves.is_empty() {
+        leaves.push(character.to_string());
     }
 
     leaves
 }
 
+/// Decompose a character to its leaves and count how often each leaf occurs
+pub fn decompose_to_leaf_counts(
+    character: &str,
+    ids_map: &HashMap<String, String>,
+    granularity: &Granularity,
+) -> LeafCounts {
+    let mut path = Vec::new();
+    count_components(&decompose_to_leaves(character, ids_map, granularity, &mut path))
+}
+
+/// The stored leaves of a character, or the character itself if it does not decompose
+pub fn leaves_of(character: &str, decompositions: &HashMap<String, CharacterDecomposition>) -> Vec<String> {
+    match decompositions.get(character) {
+        Some(decomposition) if !decomposition.leaves.is_empty() => decomposition.leaves.clone(),
+        _ => vec![character.to_string()],
+    }
+}
+
+/// Find cycles in the IDS data (e.g. A → ⿰B… and B → ⿱A…)
+/// Each cycle is reported once, rotated so that its smallest member comes first.
+/// Characters are visited in sorted order so the report is deterministic.
+pub fn find_ids_cycles(ids_map: &HashMap<String, String>) -> Vec<Vec<String>> {
+    let mut characters: Vec<&String> = ids_map.keys().collect();
+    characters.sort();
+
+    let mut finished = HashSet::new();
+    let mut path = Vec::new();
+    let mut cycles = BTreeSet::new();
+
+    for character in characters {
+        collect_cycles(character, ids_map, &mut path, &mut finished, &mut cycles);
+    }
+
+    cycles.into_iter().collect()
+}
+
+fn collect_cycles(
+    character: &str,
+    ids_map: &HashMap<String, String>,
+    path: &mut Vec<String>,
+    finished: &mut HashSet<String>,
+    cycles: &mut BTreeSet<Vec<String>>,
+) {
+    if let Some(start) = path.iter().position(|ancestor| ancestor == character) {
+        let mut cycle = path[start..].to_vec();
+        let smallest = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+        cycle.rotate_left(smallest);
+        cycles.insert(cycle);
+        return;
+    }
+    if finished.contains(character) {
+        return;
+    }
+    let Some(ids) = ids_map.get(character) else {
+        return;
+    };
+
+    path.push(character.to_string());
+    for component in extract_components_from_ids(ids) {
+        collect_cycles(&component, ids_map, path, finished, cycles);
+    }
+    path.pop();
+    finished.insert(character.to_string());
+}
+
+/// Count occurrences of each component in a list
+pub fn count_components(components: &[String]) -> LeafCounts {
+    let mut counts = LeafCounts::new();
+    for component in components {
+        *counts.entry(component.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Validate every IDS entry with the strict parser
+/// Sorted by character so the report is deterministic
+pub fn find_malformed_ids(ids_map: &HashMap<String, String>) -> Vec<MalformedIds> {
+    let mut malformed: Vec<MalformedIds> = ids_map
+        .iter()
+        .filter_map(|(character, ids)| {
+            Ids::parse(ids).err().map(|e| MalformedIds {
+                character: character.clone(),
+                ids: ids.clone(),
+                error: e.to_string(),
+            })
+        })
+        .collect();
+    malformed.sort_by(|a, b| a.character.cmp(&b.character));
+    malformed
+}
+
+/// Build the recursive decomposition tree of a character, down to its leaves
+/// Uses the same per-path cycle rule and granularity stops as decompose_to_leaves, so the
+/// tree's leaves are the character's leaves and the root's leaf_count is their number
+pub fn build_decomposition_tree(
+    character: &str,
+    ids_map: &HashMap<String, String>,
+    granularity: &Granularity,
+) -> Option<DecompositionNode> {
+    let tree = Ids::parse(ids_map.get(character)?).ok()?;
+    let mut path = vec![character.to_string()];
+    Some(node_from_ids(&tree, Some(character.to_string()), None, ids_map, granularity, &mut path))
+}
+
+/// Node for a named component, expanded through its own IDS entry when it has one
+fn node_for_component(
+    component: &str,
+    position: Option<Position>,
+    ids_map: &HashMap<String, String>,
+    granularity: &Granularity,
+    path: &mut Vec<String>,
+) -> DecompositionNode {
+    let is_leaf = path.iter().any(|ancestor| ancestor == component) || granularity.stops_at(component, path.len());
+    let tree = ids_map.get(component).and_then(|ids| Ids::parse(ids).ok());
+
+    match tree {
+        Some(tree) if !is_leaf => {
+            path.push(component.to_string());
+            let node = node_from_ids(&tree, Some(component.to_string()), position, ids_map, granularity, path);
+            path.pop();
+            node
+        }
+        _ => DecompositionNode {
+            component: Some(component.to_string()),
+            operator: None,
+            position,
+            leaf_count: 1,
+            children: Vec::new(),
+        },
+    }
+}
+
+fn node_from_ids(
+    ids: &Ids,
+    component: Option<String>,
+    position: Option<Position>,
+    ids_map: &HashMap<String, String>,
+    granularity: &Granularity,
+    path: &mut Vec<String>,
+) -> DecompositionNode {
+    let (operator, children) = match ids {
+        Ids::Compound { operator, children, .. } => {
+            let children: Vec<DecompositionNode> = children
+                .iter()
+                .zip(operator.positions())
+                .map(|(child, &child_position)| match child {
+                    Ids::Compound { .. } => {
+                        node_from_ids(child, None, Some(child_position), ids_map, granularity, path)
+                    }
+                    Ids::Char(c) => {
+                        node_for_component(&c.to_string(), Some(child_position), ids_map, granularity, path)
+                    }
+                    Ids::Entity(entity) => {
+                        node_for_component(entity, Some(child_position), ids_map, granularity, path)
+                    }
+                })
+                .collect();
+            (Some(operator.as_char()), children)
+        }
+        // The IDS is a single other character (a plain variant mapping)
+        Ids::Char(c) => (None, vec![node_for_component(&c.to_string(), None, ids_map, granularity, path)]),
+        Ids::Entity(entity) => (None, vec![node_for_component(entity, None, ids_map, granularity, path)]),
+    };
+
+    DecompositionNode {
+        component,
+        operator,
+        position,
+        leaf_count: children.iter().map(|child| child.leaf_count).sum(),
+        children,
+    }
+}
+
 /// Build character decomposition data
 pub fn build_char_decompositions(
     ids_map: &HashMap<String, String>,
+    granularity: &Granularity,
 ) -> HashMap<String, CharacterDecomposition> {
     let mut decompositions = HashMap::new();
 
     for (character, ids) in ids_map {
         let components = extract_components_from_ids(ids);
+        let leaves = decompose_to_leaves(character, ids_map, granularity, &mut Vec::new());
 
         decompositions.insert(
             character.clone(),
@@ -220,6 +823,8 @@ pub fn build_char_decompositions(
                 character: character.clone(),
                 ids: ids.clone(),
                 components,
+                leaves,
+                tree: build_decomposition_tree(character, ids_map, granularity),
             },
         );
     }
@@ -228,38 +833,29 @@ pub fn build_char_decompositions(
 }
 
 /// Build reverse mapping: components → characters
-/// This includes BOTH direct components AND all possible subsets of leaf components
+/// This includes BOTH direct components AND the full multiset of leaf components
 pub fn build_components_to_chars(
     decompositions: &HashMap<String, CharacterDecomposition>,
-    ids_map: &HashMap<String, String>,
 ) -> HashMap<String, Vec<String>> {
     let mut components_map: HashMap<String, Vec<String>> = HashMap::new();
 
     for (character, decomp) in decompositions {
         // Add direct components mapping
-        let mut sorted_components = decomp.components.clone();
-        sorted_components.sort();
-        let key = sorted_components.join("");
+        // Keys fold radical variants, so 氵 and 水 select the same characters
+        let key = key_from_counts(&count_components(&decomp.components));
 
         components_map
-            .entry(key)
-            .or_insert_with(Vec::new)
+            .entry(key.clone())
+            .or_default()
             .push(character.clone());
 
         // ALSO add leaf components mapping
         // This allows building characters from their leaf components
-        let mut visited = HashSet::new();
-        let leaf_components = decompose_to_leaves(character, ids_map, &mut visited);
-
-        // Convert HashSet to Vec for comparison and sorting
-        let mut leaf_vec: Vec<String> = leaf_components.into_iter().collect();
-        if !leaf_vec.is_empty() && leaf_vec != decomp.components {
-            leaf_vec.sort();
-            let leaf_key = leaf_vec.join("");
-
+        let leaf_key = key_from_counts(&count_components(&decomp.leaves));
+        if !decomp.leaves.is_empty() && leaf_key != key {
             components_map
                 .entry(leaf_key)
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(character.clone());
         }
     }
@@ -268,20 +864,28 @@ pub fn build_components_to_chars(
 }
 
 /// Extract all leaf components from HSK words
+/// Returns each leaf with the largest number of copies any single HSK character needs,
+/// so the component palette can offer enough duplicates (e.g. 口 → 3 because of 品)
+/// Radical variants are folded, so 氵 in 海 and 水 in 泉 count as one component
 pub fn extract_allowed_components(
     hsk_words: &HashMap<u8, Vec<String>>,
-    ids_map: &HashMap<String, String>,
-) -> HashSet<String> {
-    let mut allowed_components = HashSet::new();
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> HashMap<String, usize> {
+    let mut allowed_components: HashMap<String, usize> = HashMap::new();
 
     for words in hsk_words.values() {
         for word in words {
             // Decompose each character in the word
             for character in word.chars() {
                 let char_str = character.to_string();
-                let mut visited = HashSet::new();
-                let leaves = decompose_to_leaves(&char_str, ids_map, &mut visited);
-                allowed_components.extend(leaves);
+                let leaves: Vec<String> = leaves_of(&char_str, decompositions)
+                    .iter()
+                    .map(|leaf| fold_component(leaf).into_owned())
+                    .collect();
+                for (leaf, count) in count_components(&leaves) {
+                    let max_count = allowed_components.entry(leaf).or_insert(0);
+                    *max_count = (*max_count).max(count);
+                }
             }
         }
     }
@@ -289,28 +893,281 @@ pub fn extract_allowed_components(
     allowed_components
 }
 
+/// The glyphs HSK characters use for each folded leaf component, where any differs from it
+/// (水 → [氵, 水]); clients show these glyphs on cards rather than the folded form
+pub fn extract_component_forms(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    let mut forms: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for character in hsk_words.values().flatten().flat_map(|word| word.chars()) {
+        for leaf in leaves_of(&character.to_string(), decompositions) {
+            forms.entry(fold_component(&leaf).into_owned()).or_default().insert(leaf);
+        }
+    }
+    forms.retain(|canonical, glyphs| glyphs.iter().any(|glyph| glyph != canonical));
+    forms
+}
+
+/// Levels of named components below `character` (0 for a leaf), guarding against cycles
+/// Components where `granularity` stops count as leaves, as they do in `leaves`
+fn decomposition_depth(
+    character: &str,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    granularity: &Granularity,
+    path: &mut Vec<String>,
+) -> usize {
+    let Some(decomposition) = decompositions.get(character) else {
+        return 0;
+    };
+    if path.iter().any(|ancestor| ancestor == character) || granularity.stops_at(character, path.len()) {
+        return 0;
+    }
+
+    path.push(character.to_string());
+    let depth = decomposition
+        .components
+        .iter()
+        .map(|component| decomposition_depth(component, decompositions, granularity, path))
+        .max()
+        .unwrap_or(0);
+    path.pop();
+    depth + 1
+}
+
+/// Characters other than the intended one that each combination on the way to
+/// `character` could produce, summed over the character and its intermediates
+fn combination_ambiguity(
+    character: &str,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    path: &mut Vec<String>,
+) -> usize {
+    let Some(decomposition) = decompositions.get(character) else {
+        return 0;
+    };
+    if path.iter().any(|ancestor| ancestor == character) {
+        return 0;
+    }
+
+    let key = key_from_counts(&count_components(&decomposition.components));
+    let alternatives = components_to_chars
+        .get(&key)
+        .map_or(0, |characters| characters.len().saturating_sub(1));
+
+    path.push(character.to_string());
+    let below: usize = decomposition
+        .components
+        .iter()
+        .map(|component| combination_ambiguity(component, decompositions, components_to_chars, path))
+        .sum();
+    path.pop();
+    alternatives + below
+}
+
+/// Score every character of the HSK words
+pub fn score_difficulty(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    solvability: &SolvabilityReport,
+    stroke_counts: &HashMap<String, u32>,
+    granularity: &Granularity,
+) -> BTreeMap<String, Difficulty> {
+    let characters: BTreeSet<String> = hsk_words
+        .values()
+        .flatten()
+        .flat_map(|word| word.chars())
+        .map(String::from)
+        .collect();
+
+    characters
+        .into_iter()
+        .map(|character| {
+            let depth = decomposition_depth(&character, decompositions, granularity, &mut Vec::new());
+            let leaf_count = leaves_of(&character, decompositions).len();
+            let build_steps = solvability.solvable.get(&character).copied();
+            let ambiguity =
+                combination_ambiguity(&character, decompositions, components_to_chars, &mut Vec::new());
+            let stroke_count = stroke_counts.get(&character).copied();
+
+            let [w_depth, w_leaves, w_steps, w_ambiguity, w_strokes] = DIFFICULTY_WEIGHTS;
+            let score = w_depth * depth as f64
+                + w_leaves * leaf_count as f64
+                // Unbuildable characters are at least as hard as their leaf count suggests
+                + w_steps * build_steps.unwrap_or(leaf_count) as f64
+                + w_ambiguity * ambiguity as f64
+                + w_strokes * stroke_count.unwrap_or(0) as f64;
+
+            let difficulty = Difficulty {
+                depth,
+                leaf_count,
+                build_steps,
+                ambiguity,
+                stroke_count,
+                score,
+            };
+            (character, difficulty)
+        })
+        .collect()
+}
+
 /// Build complete game data
 pub fn build_game_data(
     hsk_words: HashMap<u8, Vec<String>>,
-    ids_map: HashMap<String, String>,
+    ids_sources: Vec<IdsSource>,
+    ids_policy: &IdsPolicy,
+    entity_table: &HashMap<String, String>,
+    character_facts: &CharacterFacts,
+    granularity: &Granularity,
 ) -> GameData {
+    println!("🔧 Summarizing IDS sources...");
+    let ids_source_summary =
+        summarize_ids_sources(&ids_sources, &hsk_words, ids_policy, entity_table, granularity);
+    for summary in &ids_source_summary {
+        println!(
+            "  📊 {}: {} entries, resolves {} HSK leaves ({} leaves after)",
+            summary.source,
+            summary.entries,
+            summary.resolved_leaves.len(),
+            summary.leaves_after
+        );
+    }
+    let ids_alternatives = merge_ids_sources(&ids_sources);
+
+    println!("🔧 Selecting IDS descriptions...");
+    let ids_map = select_ids(&ids_alternatives, ids_policy);
+    println!("  ✅ Selected {} descriptions", ids_map.len());
+
+    println!("🔧 Resolving entity references...");
+    let (ids_map, entity_report) = resolve_entities(&ids_map, entity_table);
+    println!("  ✅ Resolved {} entity references", entity_report.mappings.len());
+    if !entity_report.unresolved.is_empty() {
+        println!("  ⚠️  {} entity references could not be resolved", entity_report.unresolved.len());
+    }
+    let ids_alternatives: BTreeMap<String, Vec<IdsAlternative>> = ids_alternatives
+        .into_iter()
+        .filter(|(_, alternatives)| alternatives.len() > 1)
+        .collect();
+
     println!("🔧 Building character decompositions...");
-    let char_to_decomposition = build_char_decompositions(&ids_map);
+    let char_to_decomposition = build_char_decompositions(&ids_map, granularity);
     println!("  ✅ Built {} character decompositions", char_to_decomposition.len());
 
     println!("🔧 Building components → characters mapping...");
-    let components_to_chars = build_components_to_chars(&char_to_decomposition, &ids_map);
+    let components_to_chars = build_components_to_chars(&char_to_decomposition);
     println!("  ✅ Built {} component combinations", components_to_chars.len());
 
     println!("🔧 Extracting allowed components from HSK words...");
-    let allowed_components = extract_allowed_components(&hsk_words, &ids_map);
+    let allowed_component_counts = extract_allowed_components(&hsk_words, &char_to_decomposition);
+    let allowed_components: HashSet<String> = allowed_component_counts.keys().cloned().collect();
     println!("  ✅ Found {} unique leaf components", allowed_components.len());
+    let entity_leaves = allowed_components.iter().filter(|c| c.starts_with('&')).count();
+    if entity_leaves > 0 {
+        println!("  ⚠️  {} leaf components are still unresolved entity references", entity_leaves);
+    }
+    let repeated = allowed_component_counts.values().filter(|&&count| count > 1).count();
+    println!("  ✅ {} components appear more than once in a single character", repeated);
+    let component_forms = extract_component_forms(&hsk_words, &char_to_decomposition);
+    println!("  ✅ Folded radical variants into {} components", component_forms.len());
+
+    println!("🔧 Checking IDS data for cycles...");
+    let ids_cycles = find_ids_cycles(&ids_map);
+    if ids_cycles.is_empty() {
+        println!("  ✅ No cycles found");
+    } else {
+        println!("  ⚠️  Found {} cycles in IDS data:", ids_cycles.len());
+        for cycle in &ids_cycles {
+            println!("    {} → {}", cycle.join(" → "), cycle[0]);
+        }
+    }
+
+    println!("🔧 Validating IDS syntax...");
+    let malformed_ids = find_malformed_ids(&ids_map);
+    if malformed_ids.is_empty() {
+        println!("  ✅ All IDS entries are well-formed");
+    } else {
+        println!("  ⚠️  {} malformed IDS entries:", malformed_ids.len());
+        for entry in malformed_ids.iter().take(10) {
+            println!("    {} {} ({})", entry.character, entry.ids, entry.error);
+        }
+    }
+
+    println!("🔧 Verifying HSK characters can be built...");
+    let solvability = verify_hsk_characters(&hsk_words, &components_to_chars, &char_to_decomposition);
+    println!("  ✅ {} characters can be built", solvability.solvable.len());
+    if !solvability.unsolvable.is_empty() {
+        println!(
+            "  ⚠️  {} characters cannot be built two or three cards at a time: {}",
+            solvability.unsolvable.len(),
+            solvability.unsolvable.iter().take(20).cloned().collect::<Vec<_>>().join(" ")
+        );
+    }
+    if !solvability.too_complex.is_empty() {
+        println!("  ⚠️  Gave up on {} characters", solvability.too_complex.len());
+    }
+
+    println!("🔧 Scoring character difficulty...");
+    let difficulty = score_difficulty(
+        &hsk_words,
+        &char_to_decomposition,
+        &components_to_chars,
+        &solvability,
+        &character_facts.stroke_counts,
+        granularity,
+    );
+    println!("  ✅ Scored {} characters", difficulty.len());
+
+    println!("🔧 Ordering the curriculum by component dependencies...");
+    let curriculum = build_curriculum(&hsk_words, &char_to_decomposition, &difficulty);
+    println!("  ✅ Ordered {} levels", curriculum.levels.len());
+    if !curriculum.late_prerequisites.is_empty() {
+        println!(
+            "  ⚠️  {} prerequisites are only taught at a higher level",
+            curriculum.late_prerequisites.len()
+        );
+    }
+    if !curriculum.cycle_breaks.is_empty() {
+        println!("  ⚠️  Broke {} dependency cycles", curriculum.cycle_breaks.len());
+    }
+
+    println!("🔧 Merging semantic/phonetic labels with IDS decompositions...");
+    let component_roles =
+        build_component_roles(&hsk_words, &char_to_decomposition, &character_facts.component_annotations);
+    let with_phonetic = component_roles.values().filter(|roles| !roles.phonetic.is_empty()).count();
+    println!(
+        "  ✅ Labelled {} characters, {} with a phonetic component",
+        component_roles.len(),
+        with_phonetic
+    );
+
+    println!("🔧 Grouping characters into phonetic series...");
+    let phonetic_series =
+        build_phonetic_series(&hsk_words, &component_roles, &character_facts.pinyin, MIN_SERIES_MEMBERS);
+    let regular = phonetic_series.values().filter(|series| series.regularity >= 0.5).count();
+    println!(
+        "  ✅ Found {} phonetic series, {} mostly sharing a syllable",
+        phonetic_series.len(),
+        regular
+    );
 
     GameData {
         char_to_decomposition,
         components_to_chars,
         allowed_components,
+        allowed_component_counts,
+        component_forms,
         hsk_words,
+        ids_cycles,
+        malformed_ids,
+        ids_alternatives,
+        ids_source_summary,
+        entity_report,
+        solvability,
+        difficulty,
+        curriculum,
+        component_roles,
+        phonetic_series,
     }
 }
 
@@ -332,6 +1189,13 @@ pub fn save_game_data(game_data: &GameData, output_dir: &str) -> Result<()> {
     file.write_all(json.as_bytes())?;
     println!("  ✅ Saved components mapping to {}", comp_path);
 
+    // Save the component key format so clients build identical keys
+    let spec_path = format!("{}/components_key_spec.json", output_dir);
+    let mut file = File::create(&spec_path)?;
+    let json = serde_json::to_string_pretty(&component_key_spec())?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved component key spec to {}", spec_path);
+
     // Save allowed components
     let allowed_path = format!("{}/allowed_components.json", output_dir);
     let mut file = File::create(&allowed_path)?;
@@ -339,6 +1203,20 @@ pub fn save_game_data(game_data: &GameData, output_dir: &str) -> Result<()> {
     file.write_all(json.as_bytes())?;
     println!("  ✅ Saved allowed components to {}", allowed_path);
 
+    // Save allowed component multiplicities
+    let counts_path = format!("{}/allowed_component_counts.json", output_dir);
+    let mut file = File::create(&counts_path)?;
+    let json = serde_json::to_string_pretty(&game_data.allowed_component_counts)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved allowed component counts to {}", counts_path);
+
+    // Save the radical variant glyphs behind each folded component
+    let forms_path = format!("{}/component_forms.json", output_dir);
+    let mut file = File::create(&forms_path)?;
+    let json = serde_json::to_string_pretty(&game_data.component_forms)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved component forms to {}", forms_path);
+
     // Save HSK words
     let hsk_path = format!("{}/hsk_words.json", output_dir);
     let mut file = File::create(&hsk_path)?;
@@ -346,9 +1224,94 @@ pub fn save_game_data(game_data: &GameData, output_dir: &str) -> Result<()> {
     file.write_all(json.as_bytes())?;
     println!("  ✅ Saved HSK words to {}", hsk_path);
 
+    // Save IDS cycle report
+    let cycles_path = format!("{}/ids_cycles.json", output_dir);
+    let mut file = File::create(&cycles_path)?;
+    let json = serde_json::to_string_pretty(&game_data.ids_cycles)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved IDS cycle report to {}", cycles_path);
+
+    // Save solvability report
+    let solvability_path = format!("{}/solvability.json", output_dir);
+    let mut file = File::create(&solvability_path)?;
+    let json = serde_json::to_string_pretty(&game_data.solvability)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved solvability report to {}", solvability_path);
+
+    // Save difficulty scores
+    let difficulty_path = format!("{}/difficulty.json", output_dir);
+    let mut file = File::create(&difficulty_path)?;
+    let json = serde_json::to_string_pretty(&game_data.difficulty)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved difficulty scores to {}", difficulty_path);
+
+    // Save curriculum
+    let curriculum_path = format!("{}/curriculum.json", output_dir);
+    let mut file = File::create(&curriculum_path)?;
+    let json = serde_json::to_string_pretty(&game_data.curriculum)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved curriculum to {}", curriculum_path);
+
+    // Save semantic/phonetic component labels
+    let roles_path = format!("{}/component_roles.json", output_dir);
+    let mut file = File::create(&roles_path)?;
+    let json = serde_json::to_string_pretty(&game_data.component_roles)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved component roles to {}", roles_path);
+
+    // Save phonetic series
+    let series_path = format!("{}/phonetic_series.json", output_dir);
+    let mut file = File::create(&series_path)?;
+    let json = serde_json::to_string_pretty(&game_data.phonetic_series)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved phonetic series to {}", series_path);
+
+    // Save malformed IDS report
+    let malformed_path = format!("{}/malformed_ids.json", output_dir);
+    let mut file = File::create(&malformed_path)?;
+    let json = serde_json::to_string_pretty(&game_data.malformed_ids)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved malformed IDS report to {}", malformed_path);
+
+    // Save alternative IDS descriptions
+    let alternatives_path = format!("{}/ids_alternatives.json", output_dir);
+    let mut file = File::create(&alternatives_path)?;
+    let json = serde_json::to_string_pretty(&game_data.ids_alternatives)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved alternative IDS descriptions to {}", alternatives_path);
+
+    // Save IDS source summary
+    let sources_path = format!("{}/ids_sources.json", output_dir);
+    let mut file = File::create(&sources_path)?;
+    let json = serde_json::to_string_pretty(&game_data.ids_source_summary)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved IDS source summary to {}", sources_path);
+
+    // Save entity reference mapping
+    let entity_path = format!("{}/entity_map.json", output_dir);
+    let mut file = File::create(&entity_path)?;
+    let json = serde_json::to_string_pretty(&game_data.entity_report)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved entity reference mapping to {}", entity_path);
+
     Ok(())
 }
 
+/// An IDS map from (character, IDS) pairs, for tests
+#[cfg(test)]
+pub fn ids_map_from(entries: &[(&str, &str)]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .map(|(character, ids)| (character.to_string(), ids.to_string()))
+        .collect()
+}
+
+/// Decompositions of (character, IDS) pairs at the default granularity, for tests
+#[cfg(test)]
+pub fn decompositions_from(entries: &[(&str, &str)]) -> HashMap<String, CharacterDecomposition> {
+    build_char_decompositions(&ids_map_from(entries), &Granularity::default())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -422,4 +1385,470 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_leaf_counts_preserve_repeated_components() {
+        let ids_map = ids_map_from(&[("林", "⿰木木"), ("品", "⿱口吅"), ("吅", "⿰口口")]);
+
+        let lin = decompose_to_leaf_counts("林", &ids_map, &Granularity::default());
+        assert_eq!(lin.get("木"), Some(&2));
+
+        let pin = decompose_to_leaf_counts("品", &ids_map, &Granularity::default());
+        assert_eq!(pin.len(), 1);
+        assert_eq!(pin.get("口"), Some(&3));
+    }
+
+    #[test]
+    fn test_components_to_chars_keys_include_duplicates() {
+        let ids_map = ids_map_from(&[("林", "⿰木木"), ("品", "⿱口吅"), ("吅", "⿰口口")]);
+        let decompositions = build_char_decompositions(&ids_map, &Granularity::default());
+        let components_to_chars = build_components_to_chars(&decompositions);
+
+        assert_eq!(components_to_chars.get("木|木"), Some(&vec!["林".to_string()]));
+        assert!(components_to_chars["口|口|口"].contains(&"品".to_string()));
+        assert!(!components_to_chars.contains_key("木"));
+    }
+
+    #[test]
+    fn test_allowed_components_record_max_multiplicity() {
+        let ids_map = ids_map_from(&[("品", "⿱口吅"), ("吅", "⿰口口"), ("叶", "⿰口十")]);
+        let hsk_words = HashMap::from([(1, vec!["品".to_string(), "叶".to_string()])]);
+
+        let decompositions = build_char_decompositions(&ids_map, &Granularity::default());
+
+        let allowed = extract_allowed_components(&hsk_words, &decompositions);
+        assert_eq!(allowed.get("口"), Some(&3));
+        assert_eq!(allowed.get("十"), Some(&1));
+    }
+
+    #[test]
+    fn test_radical_variants_are_folded_for_keys_and_palette() {
+        // 海 = 氵 + 每, 泉 = 白 + 水, 休 = 亻 + 木
+        let ids_map = ids_map_from(&[("海", "⿰氵每"), ("泉", "⿱白水"), ("休", "⿰⺅木")]);
+        let hsk_words = HashMap::from([(1, vec!["海".to_string(), "泉".to_string(), "休".to_string()])]);
+        let decompositions = build_char_decompositions(&ids_map, &Granularity::default());
+
+        // Cards keep the glyph the IDS used
+        assert_eq!(decompositions["海"].leaves, vec!["氵", "每"]);
+        let components_to_chars = build_components_to_chars(&decompositions);
+        assert_eq!(components_to_chars.get("每|水"), Some(&vec!["海".to_string()]));
+        assert_eq!(components_to_chars.get("人|木"), Some(&vec!["休".to_string()]));
+
+        let allowed = extract_allowed_components(&hsk_words, &decompositions);
+        assert_eq!(allowed.get("水"), Some(&1));
+        assert!(!allowed.contains_key("氵"));
+        assert_eq!(allowed.len(), 5);
+
+        let forms = extract_component_forms(&hsk_words, &decompositions);
+        assert_eq!(forms["水"], BTreeSet::from(["水".to_string(), "氵".to_string()]));
+        assert_eq!(forms["人"], BTreeSet::from(["⺅".to_string()]));
+        assert!(!forms.contains_key("木"));
+    }
+
+    #[test]
+    fn test_granularity_keeps_stop_components_whole() {
+        // 語 = 言 + 吾, 吾 = 五 + 口, 言 = ⿱亠⿱二口 (fragments a learner would not recognize)
+        let ids_map = ids_map_from(&[("語", "⿰言吾"), ("吾", "⿱五口"), ("言", "⿱亠⿱二口")]);
+        let hsk_words = HashMap::from([(1, vec!["語".to_string(), "言".to_string()])]);
+
+        let full = build_char_decompositions(&ids_map, &Granularity::default());
+        assert_eq!(full["語"].leaves, vec!["亠", "二", "口", "五", "口"]);
+
+        // 言 is a Kangxi radical: it stays whole inside 語 but still decomposes on its own
+        let radicals = Granularity { stops: HashSet::from(["言".to_string()]), max_depth: None };
+        let decompositions = build_char_decompositions(&ids_map, &radicals);
+        assert_eq!(decompositions["語"].leaves, vec!["言", "五", "口"]);
+        assert_eq!(decompositions["言"].leaves, vec!["亠", "二", "口"]);
+        let allowed = extract_allowed_components(&hsk_words, &decompositions);
+        // 言 is needed whole for 語, and its own parts because 言 is an HSK word too
+        assert_eq!(allowed.get("言"), Some(&1));
+        assert_eq!(allowed.get("亠"), Some(&1));
+        assert!(build_components_to_chars(&decompositions)["五|口|言"].contains(&"語".to_string()));
+
+        let shallow = Granularity { stops: HashSet::new(), max_depth: NonZeroUsize::new(1) };
+        assert_eq!(build_char_decompositions(&ids_map, &shallow)["語"].leaves, vec!["言", "吾"]);
+    }
+
+    #[test]
+    fn test_repeated_decomposable_sibling_is_decomposed() {
+        // The second 可 must not be cut short just because the first one was expanded
+        let ids_map = ids_map_from(&[("哥", "⿱可可"), ("可", "⿹丁口")]);
+
+        let mut path = Vec::new();
+        let leaves = decompose_to_leaves("哥", &ids_map, &Granularity::default(), &mut path);
+        assert_eq!(leaves, vec!["丁", "口", "丁", "口"]);
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn test_shared_subpart_in_two_branches() {
+        let ids_map = ids_map_from(&[("X", "⿰AB"), ("A", "⿱C口"), ("B", "⿱C木"), ("C", "⿰十一")]);
+
+        let counts = decompose_to_leaf_counts("X", &ids_map, &Granularity::default());
+        assert_eq!(counts.get("十"), Some(&2));
+        assert_eq!(counts.get("一"), Some(&2));
+        assert!(!counts.contains_key("C"));
+    }
+
+    #[test]
+    fn test_cycles_are_leaves_and_reported() {
+        let ids_map = ids_map_from(&[("A", "⿰B口"), ("B", "⿱A木"), ("C", "⿰B日")]);
+
+        let counts = decompose_to_leaf_counts("A", &ids_map, &Granularity::default());
+        assert_eq!(counts, count_components(&["A".into(), "木".into(), "口".into()]));
+
+        let cycles = find_ids_cycles(&ids_map);
+        assert_eq!(cycles, vec![vec!["A".to_string(), "B".to_string()]]);
+    }
+
+    #[test]
+    fn test_find_malformed_ids() {
+        let ids_map = ids_map_from(&[("明", "⿰日月"), ("X", "⿰日"), ("Y", "⿱口口口")]);
+
+        let malformed = find_malformed_ids(&ids_map);
+        let characters: Vec<&str> = malformed.iter().map(|m| m.character.as_str()).collect();
+        assert_eq!(characters, vec!["X", "Y"]);
+    }
+
+    #[test]
+    fn test_decomposition_tree_structure() {
+        let ids_map = ids_map_from(&[("森", "⿱木林"), ("林", "⿰木木")]);
+
+        let tree = build_decomposition_tree("森", &ids_map, &Granularity::default()).unwrap();
+        assert_eq!(tree.component.as_deref(), Some("森"));
+        assert_eq!(tree.operator, Some('⿱'));
+        assert_eq!(tree.position, None);
+        assert_eq!(tree.leaf_count, 3);
+
+        let top = &tree.children[0];
+        assert_eq!(top.component.as_deref(), Some("木"));
+        assert_eq!(top.position, Some(Position::Top));
+        assert!(top.children.is_empty());
+
+        let bottom = &tree.children[1];
+        assert_eq!(bottom.component.as_deref(), Some("林"));
+        assert_eq!(bottom.operator, Some('⿰'));
+        assert_eq!(bottom.leaf_count, 2);
+        assert_eq!(bottom.children[1].position, Some(Position::Right));
+    }
+
+    #[test]
+    fn test_decomposition_tree_nested_sequence_and_leaf_count() {
+        let ids_map = ids_map_from(&[("品", "⿱口⿰口口"), ("A", "⿰B口"), ("B", "⿱A木")]);
+
+        let tree = build_decomposition_tree("品", &ids_map, &Granularity::default()).unwrap();
+        let nested = &tree.children[1];
+        assert_eq!(nested.component, None);
+        assert_eq!(nested.operator, Some('⿰'));
+        assert_eq!(tree.leaf_count, 3);
+
+        // Cycles stop the same way as decompose_to_leaves
+        let cyclic = build_decomposition_tree("A", &ids_map, &Granularity::default()).unwrap();
+        let mut path = Vec::new();
+        assert_eq!(cyclic.leaf_count, decompose_to_leaves("A", &ids_map, &Granularity::default(), &mut path).len());
+    }
+
+    /// Named leaf nodes of a tree, left to right
+    fn tree_leaves(node: &DecompositionNode) -> Vec<String> {
+        if node.children.is_empty() {
+            return node.component.iter().cloned().collect();
+        }
+        node.children.iter().flat_map(tree_leaves).collect()
+    }
+
+    #[test]
+    fn test_decomposition_tree_follows_granularity() {
+        let ids_map = ids_map_from(&[("語", "⿰言吾"), ("吾", "⿱五口"), ("言", "⿱亠⿱二口")]);
+        let granularities = [
+            Granularity::default(),
+            Granularity { stops: HashSet::from(["言".to_string()]), max_depth: None },
+            Granularity { stops: HashSet::new(), max_depth: NonZeroUsize::new(1) },
+        ];
+
+        for granularity in &granularities {
+            let decompositions = build_char_decompositions(&ids_map, granularity);
+            for decomposition in decompositions.values() {
+                let tree = decomposition.tree.as_ref().unwrap();
+                assert_eq!(tree_leaves(tree), decomposition.leaves);
+                assert_eq!(tree.leaf_count, decomposition.leaves.len());
+            }
+        }
+
+        // 言 stays a single node inside 語, but still expands as a character of its own
+        let decompositions = build_char_decompositions(&ids_map, &granularities[1]);
+        let yan = &decompositions["語"].tree.as_ref().unwrap().children[0];
+        assert_eq!(yan.component.as_deref(), Some("言"));
+        assert!(yan.children.is_empty());
+        assert_eq!(decompositions["言"].tree.as_ref().unwrap().leaf_count, 3);
+    }
+
+    fn alternative(ids: &str, regions: &str, apparent: bool) -> IdsAlternative {
+        IdsAlternative {
+            ids: ids.to_string(),
+            source: "test".to_string(),
+            regions: regions.to_string(),
+            apparent,
+        }
+    }
+
+    #[test]
+    fn test_split_region_tag() {
+        assert_eq!(split_region_tag("⿰氵每[GTKV]"), ("⿰氵每", "GTKV"));
+        assert_eq!(split_region_tag("⿰日月"), ("⿰日月", ""));
+    }
+
+    #[test]
+    fn test_parse_ids_file_keeps_alternatives() {
+        let path = std::env::temp_dir().join("ids_alternatives_test.txt");
+        std::fs::write(
+            &path,
+            ";; comment\nU+4E00\t一\t一\nU+5173\t关\t⿱䒑大\t@apparent=⿱丷天\nU+6D77\t海\t⿰氵每[GJK]\t⿰氵毎[T]\t?\n",
+        )
+        .unwrap();
+
+        let source = parse_ids_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+        let ids_map = source.entries;
+
+        assert!(!ids_map.contains_key("一"));
+        assert_eq!(source.atomic, HashSet::from(["一".to_string()]));
+        assert_eq!(ids_map["关"], vec![
+            IdsAlternative { source: "ids_alternatives_test".to_string(), ..alternative("⿱䒑大", "", false) },
+            IdsAlternative { source: "ids_alternatives_test".to_string(), ..alternative("⿱丷天", "", true) },
+        ]);
+        let hai: Vec<(&str, &str)> = ids_map["海"].iter().map(|a| (a.ids.as_str(), a.regions.as_str())).collect();
+        assert_eq!(hai, vec![("⿰氵每", "GJK"), ("⿰氵毎", "T")]);
+    }
+
+    #[test]
+    fn test_policy_prefers_region_then_untagged() {
+        let alternatives = vec![
+            alternative("⿰氵每", "GJK", false),
+            alternative("⿰氵毎", "", false),
+            alternative("⿰氵𣫴", "T", false),
+        ];
+
+        let traditional = IdsPolicy::default();
+        assert_eq!(traditional.select(&alternatives).unwrap().ids, "⿰氵𣫴");
+
+        let simplified = IdsPolicy { preferred_regions: vec!['G'], use_apparent: false };
+        assert_eq!(simplified.select(&alternatives).unwrap().ids, "⿰氵每");
+
+        let japanese_first = IdsPolicy { preferred_regions: vec!['V'], use_apparent: false };
+        assert_eq!(japanese_first.select(&alternatives).unwrap().ids, "⿰氵毎");
+    }
+
+    #[test]
+    fn test_policy_apparent_descriptions() {
+        let only_apparent = vec![alternative("⿰丿乚", "", true)];
+        assert_eq!(IdsPolicy::default().select(&only_apparent), None);
+
+        let both = vec![alternative("⿱䒑大", "", false), alternative("⿱丷天", "", true)];
+        assert_eq!(IdsPolicy::default().select(&both).unwrap().ids, "⿱䒑大");
+        let apparent = IdsPolicy { use_apparent: true, ..IdsPolicy::default() };
+        assert_eq!(apparent.select(&both).unwrap().ids, "⿱丷天");
+    }
+
+    fn source_from(path: &str, entries: &[(&str, &str)]) -> IdsSource {
+        IdsSource {
+            path: path.to_string(),
+            entries: entries
+                .iter()
+                .map(|(character, ids)| (character.to_string(), vec![alternative(ids, "", false)]))
+                .collect(),
+            atomic: HashSet::new(),
+            dropped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_later_sources_do_not_decompose_atomic_characters() {
+        let mut basic = source_from("basic", &[("錢", "⿰金戔")]);
+        basic.atomic = HashSet::from(["金".to_string()]);
+        let jis = source_from("jis", &[("金", "⿱&CDP-8AFC;⿻土丷"), ("竹", "⿰亇&C3-212D;")]);
+
+        let merged = merge_ids_sources(&[basic, jis]);
+        assert!(!merged.contains_key("金"));
+        // Only atomic in an earlier file counts
+        assert_eq!(merged["竹"][0].ids, "⿰亇&C3-212D;");
+        assert_eq!(merged["錢"][0].ids, "⿰金戔");
+    }
+
+    #[test]
+    fn test_merge_keeps_earlier_sources_first() {
+        let sources = vec![
+            source_from("basic", &[("學", "⿱𦥯子")]),
+            source_from("ext-b", &[("學", "⿱⿳&CDP-8CC6;冖子"), ("𦥯", "⿳⿲𦥑爻𦥑冖")]),
+        ];
+
+        let merged = merge_ids_sources(&sources);
+        assert_eq!(merged["學"].len(), 2);
+        assert_eq!(select_ids(&merged, &IdsPolicy::default())["學"], "⿱𦥯子");
+    }
+
+    #[test]
+    fn test_source_summary_counts_resolved_leaves() {
+        let sources = vec![
+            source_from("basic", &[("學", "⿱𦥯子")]),
+            source_from("ext-b", &[("𦥯", "⿱⿲𦥑爻𦥑冖")]),
+            source_from("cdp", &[]),
+        ];
+        let hsk_words = HashMap::from([(1, vec!["學".to_string(), "子".to_string()])]);
+
+        let summary = summarize_ids_sources(
+            &sources,
+            &hsk_words,
+            &IdsPolicy::default(),
+            &HashMap::new(),
+            &Granularity::default(),
+        );
+        assert_eq!(summary[0].resolved_leaves, vec!["學"]);
+        assert_eq!(summary[0].leaves_after, 2); // 𦥯 子
+        assert_eq!(summary[1].resolved_leaves, vec!["𦥯"]);
+        assert_eq!(summary[1].leaves_after, 4); // 𦥑 爻 冖 子
+        assert!(summary[2].resolved_leaves.is_empty());
+    }
+
+    #[test]
+    fn test_source_summary_matches_the_build() {
+        let sources = vec![
+            source_from("basic", &[("學", "⿱𦥯子")]),
+            source_from("ext-b", &[("𦥯", "⿱⿲𦥑爻&U-i001+81FC;冖")]),
+        ];
+        let hsk_words = HashMap::from([(1, vec!["學".to_string()])]);
+        let summarize = |granularity: &Granularity| {
+            summarize_ids_sources(&sources, &hsk_words, &IdsPolicy::default(), &HashMap::new(), granularity)
+        };
+
+        // Entity references are resolved first, so the summary counts 臼 rather than the reference
+        let summary = summarize(&Granularity::default());
+        assert_eq!(summary[1].leaves_after, 5); // 𦥑 爻 臼 冖 子
+
+        // Where the build keeps 𦥯 whole, so does the summary
+        let granularity = Granularity {
+            stops: HashSet::from(["𦥯".to_string()]),
+            max_depth: None,
+        };
+        let summary = summarize(&granularity);
+        assert!(summary[1].resolved_leaves.is_empty());
+        assert_eq!(summary[1].leaves_after, 2);
+    }
+
+    #[test]
+    fn test_only_required_ids_files_must_exist() {
+        let missing = |required| IdsFile { path: "ids/not-downloaded.txt".to_string(), required };
+
+        assert!(load_ids_sources(&[missing(false)]).unwrap().is_empty());
+        let error = load_ids_sources(&[missing(false), missing(true)]).unwrap_err();
+        assert_eq!(error.to_string(), "IDS file ids/not-downloaded.txt not found");
+    }
+
+    #[test]
+    fn test_parse_jis_file_maps_to_unicode_and_logs_drops() {
+        let path = std::env::temp_dir().join("ids_jis_test.txt");
+        std::fs::write(
+            &path,
+            "J90-3021\t&I-J90-3021;\t\nJ90-3022\t&I-J90-3022;\t⿰口亜\nJ90-7F7F\t&I-J90-7F7F;\t⿰口口\nU+4E00\t一\t?\n",
+        )
+        .unwrap();
+
+        let source = parse_ids_file(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(source.entries["唖"][0].ids, "⿰口亜");
+        assert!(!source.entries.contains_key("&I-J90-3022;"));
+        let dropped: Vec<(&str, DropReason)> = source
+            .dropped
+            .iter()
+            .map(|d| (d.character.as_str(), d.reason))
+            .collect();
+        assert_eq!(dropped, vec![
+            ("&I-J90-3021;", DropReason::EmptyIds),
+            ("&I-J90-7F7F;", DropReason::UnmappedJis),
+            ("一", DropReason::Placeholder),
+        ]);
+    }
+
+    #[test]
+    fn test_game_data_never_contains_entity_references() {
+        let sources = vec![source_from(
+            "basic",
+            &[
+                ("明", "⿱日月"),
+                ("&CDP-8CCB;", "⿱日月"),
+                ("盟", "⿱&CDP-8CCB;皿"),
+                ("&CDP-8B7A;", "⿰亻丨"),
+                ("做", "⿰&CDP-8B7A;攵"),
+                ("森", "⿱木&GT-K00059;"),
+            ],
+        )];
+        let hsk_words = HashMap::from([(1, vec!["明".to_string(), "盟".to_string(), "做".to_string(), "森".to_string()])]);
+
+        let game_data = build_game_data(
+            hsk_words,
+            sources,
+            &IdsPolicy::default(),
+            &HashMap::new(),
+            &CharacterFacts::default(),
+            &Granularity::default(),
+        );
+
+        let decompositions = serde_json::to_string(&game_data.char_to_decomposition).unwrap();
+        let components_to_chars = serde_json::to_string(&game_data.components_to_chars).unwrap();
+        assert!(!decompositions.contains('&'), "{}", decompositions);
+        assert!(!components_to_chars.contains('&'), "{}", components_to_chars);
+        assert!(game_data.allowed_components.iter().all(|component| !component.contains('&')));
+
+        assert_eq!(game_data.components_to_chars["日|月"], vec!["明"]);
+        assert_eq!(game_data.char_to_decomposition["做"].leaves, vec!["亻", "丨", "攵"]);
+        // Its unresolved part keeps 森 whole
+        assert!(!game_data.char_to_decomposition.contains_key("森"));
+        assert_eq!(game_data.entity_report.unresolved.get("&GT-K00059;"), Some(&1));
+    }
+
+    #[test]
+    fn test_difficulty_orders_simple_before_nested_characters() {
+        // 杳 and 杲 are both 木 + 日, so combining those two is ambiguous
+        let ids_map = ids_map_from(&[
+            ("明", "⿰日月"),
+            ("森", "⿱木林"),
+            ("林", "⿰木木"),
+            ("杳", "⿱木日"),
+            ("杲", "⿱日木"),
+        ]);
+        let decompositions = build_char_decompositions(&ids_map, &Granularity::default());
+        let components_to_chars = build_components_to_chars(&decompositions);
+        let hsk_words = HashMap::from([(1, vec!["明".to_string(), "森".to_string(), "杳".to_string()])]);
+        let solvability = verify_hsk_characters(&hsk_words, &components_to_chars, &decompositions);
+        let stroke_counts = HashMap::from([("明".to_string(), 8), ("森".to_string(), 12)]);
+
+        let difficulty = score_difficulty(
+            &hsk_words,
+            &decompositions,
+            &components_to_chars,
+            &solvability,
+            &stroke_counts,
+            &Granularity::default(),
+        );
+
+        let ming = &difficulty["明"];
+        assert_eq!((ming.depth, ming.leaf_count, ming.build_steps, ming.ambiguity), (1, 2, Some(1), 0));
+        let sen = &difficulty["森"];
+        assert_eq!((sen.depth, sen.leaf_count, sen.stroke_count), (2, 3, Some(12)));
+        assert_eq!(difficulty["杳"].ambiguity, 1);
+        assert!(sen.score > ming.score);
+
+        // Kept whole, 林 adds no level below 森
+        let stop_lin = Granularity { stops: HashSet::from(["林".to_string()]), max_depth: None };
+        let decompositions = build_char_decompositions(&ids_map, &stop_lin);
+        let difficulty = score_difficulty(
+            &hsk_words,
+            &decompositions,
+            &components_to_chars,
+            &solvability,
+            &stroke_counts,
+            &stop_lin,
+        );
+        assert_eq!((difficulty["森"].depth, difficulty["森"].leaf_count), (1, 2));
+    }
 }
@@ -0,0 +1,212 @@
+// gloss_ranking.rs - Pick the best definition of a word as its clue
+//
+// A word's definitions come from several dictionary items, in source order, and include
+// entries that make poor clues: "variant of 筏[fá]", "surname Li", "see 明白[míng bai]",
+// "CL:個|个[ge4]". Each definition is scored:
+//
+// - source: curated DongChinese definitions beat CEDICT, which beats terse Unicode ones
+// - reading: for a character with several readings, definitions of the reading used most
+//   often (from pinyinFrequencies) beat those of rare readings (行 xíng before háng)
+// - length: very short or very long definitions lose a little
+// - cross-references, surnames, classifiers and archaic forms lose a lot
+//
+// The best definition is the primary clue and the rest, best first, are backups.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::chinese_types::Source;
+use crate::pinyin::normalize;
+
+/// Score of a definition from each source
+const SOURCE_SCORES: [(Source, f64); 3] = [(Source::DongChinese, 1.0), (Source::Cedict, 0.8), (Source::Unicode, 0.4)];
+
+/// Score of a definition whose item names no source
+const UNKNOWN_SOURCE_SCORE: f64 = 0.6;
+
+/// Weight of the share of a character's uses that have the definition's reading
+const READING_WEIGHT: f64 = 1.0;
+
+/// Definitions up to this many characters are not penalized for length
+const IDEAL_LENGTH: usize = 40;
+
+/// Phrases that make a definition a poor clue when they open one of its clauses, and how
+/// much they cost; "see 明白" is a cross-reference, "to see a doctor" is not
+const CLAUSE_PENALTIES: [(&str, f64); 7] = [
+    ("old variant of", 2.5),
+    ("variant of", 2.0),
+    ("cl:", 2.0),
+    ("see ", 1.5),
+    ("surname", 1.5),
+    ("used in", 1.0),
+    ("abbr. for", 0.5),
+];
+
+/// Labels that make a definition a poor clue wherever they appear
+const LABEL_PENALTIES: [(&str, f64); 2] = [("(archaic)", 0.5), ("(old)", 0.5)];
+
+/// One definition of a word, with what it is ranked by
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlossCandidate {
+    pub text: String,
+    pub source: Option<Source>,
+    /// Reading of the item the definition belongs to
+    pub reading: Option<String>,
+}
+
+/// A word's clues, saved in word_clues.json
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedClues {
+    pub primary: String,
+    /// Remaining clues, best first
+    pub backups: Vec<String>,
+}
+
+impl RankedClues {
+    /// Split a best-first list of clues; None if it is empty
+    pub fn from_ranked(clues: &[String]) -> Option<RankedClues> {
+        let (primary, backups) = clues.split_first()?;
+        Some(RankedClues {
+            primary: primary.clone(),
+            backups: backups.to_vec(),
+        })
+    }
+}
+
+/// Share of a character's uses read each way, keyed by numbered reading ("xing2" → 0.9)
+pub type ReadingShares = HashMap<String, f64>;
+
+/// Turn reading → count pairs into shares of the total
+pub fn reading_shares(frequencies: &[(String, i64)]) -> ReadingShares {
+    let total: i64 = frequencies.iter().map(|(_, count)| count.max(&0)).sum();
+    if total == 0 {
+        return ReadingShares::new();
+    }
+    frequencies
+        .iter()
+        .map(|(reading, count)| (normalize(reading), (*count).max(0) as f64 / total as f64))
+        .collect()
+}
+
+/// How good a clue `candidate` makes; higher is better
+pub fn score_gloss(candidate: &GlossCandidate, shares: Option<&ReadingShares>) -> f64 {
+    let source = candidate.source.as_ref().map_or(UNKNOWN_SOURCE_SCORE, |source| {
+        SOURCE_SCORES
+            .iter()
+            .find(|(s, _)| s == source)
+            .map_or(UNKNOWN_SOURCE_SCORE, |&(_, score)| score)
+    });
+
+    // Words without reading statistics, or definitions without a reading, are neutral
+    let reading = match (shares, &candidate.reading) {
+        (Some(shares), Some(reading)) if !shares.is_empty() => {
+            shares.get(&normalize(reading)).copied().unwrap_or(0.0)
+        }
+        _ => 1.0,
+    };
+
+    let length = candidate.text.chars().count();
+    let length_penalty = if length < 2 {
+        0.5
+    } else {
+        (length.saturating_sub(IDEAL_LENGTH) as f64 / IDEAL_LENGTH as f64).min(1.0)
+    };
+
+    let text = candidate.text.to_lowercase();
+    // Only the costliest matching phrase counts ("old variant of" also contains "variant of")
+    let phrase_penalty = CLAUSE_PENALTIES
+        .iter()
+        .filter(|(phrase, _)| opens_clause(&text, phrase))
+        .chain(LABEL_PENALTIES.iter().filter(|(label, _)| text.contains(label)))
+        .map(|&(_, penalty)| penalty)
+        .fold(0.0, f64::max);
+
+    source + READING_WEIGHT * reading - length_penalty - phrase_penalty
+}
+
+/// Whether `phrase` starts `text` or follows a "; " or "(" in it
+fn opens_clause(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(index, _)| {
+        let before = text[..index].trim_end();
+        before.is_empty() || before.ends_with(';') || before.ends_with('(')
+    })
+}
+
+/// Definitions best first; equal scores keep dictionary order
+pub fn rank_glosses(candidates: Vec<GlossCandidate>, shares: Option<&ReadingShares>) -> Vec<String> {
+    let mut scored: Vec<(f64, GlossCandidate)> = candidates
+        .into_iter()
+        .map(|candidate| (score_gloss(&candidate, shares), candidate))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, candidate)| candidate.text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(text: &str, source: Option<Source>, reading: Option<&str>) -> GlossCandidate {
+        GlossCandidate {
+            text: text.to_string(),
+            source,
+            reading: reading.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_cross_references_and_surnames_rank_last() {
+        let ranked = rank_glosses(
+            vec![
+                candidate("surname Li", Some(Source::Cedict), None),
+                candidate("old variant of 李[li3]", Some(Source::Cedict), None),
+                candidate("plum", Some(Source::Cedict), None),
+                candidate("plum; plum tree", Some(Source::DongChinese), None),
+            ],
+            None,
+        );
+        assert_eq!(ranked, vec!["plum; plum tree", "plum", "surname Li", "old variant of 李[li3]"]);
+    }
+
+    #[test]
+    fn test_phrases_only_count_at_the_start_of_a_clause() {
+        let score = |text: &str| score_gloss(&candidate(text, Some(Source::Cedict), None), None);
+        let plain = score("to visit a doctor");
+        assert_eq!(score("to see a doctor"), plain);
+        assert_eq!(score("commonly used in cooking"), score("commonly eaten with rice"));
+        assert_eq!(score("to foresee the future"), score("to predict the future"));
+
+        assert!(score("see 明白[ming2 bai5]") < plain);
+        assert!(score("clear; (used in 明白)") < score("clear; (as in 明白)"));
+        assert!(score("bright; variant of 明[ming2]") < score("bright; clear of 明[ming2]"));
+        assert!(score("to know (archaic)") < score("to know"));
+    }
+
+    #[test]
+    fn test_common_reading_wins() {
+        // 行 is read xíng far more often than háng
+        let shares = reading_shares(&[("xíng".to_string(), 90), ("háng".to_string(), 10)]);
+        assert!((shares["xing2"] - 0.9).abs() < 1e-9);
+
+        let ranked = rank_glosses(
+            vec![
+                candidate("row; line; profession", Some(Source::Cedict), Some("háng")),
+                candidate("to walk; to go; capable", Some(Source::Cedict), Some("xíng")),
+            ],
+            Some(&shares),
+        );
+        assert_eq!(ranked[0], "to walk; to go; capable");
+    }
+
+    #[test]
+    fn test_long_definitions_lose_to_short_ones_from_the_same_source() {
+        let long = "a very long definition that goes on and on well past any reasonable clue length";
+        let short = candidate("bright", Some(Source::Cedict), None);
+        assert!(score_gloss(&short, None) > score_gloss(&candidate(long, Some(Source::Cedict), None), None));
+        assert_eq!(
+            RankedClues::from_ranked(&["bright".to_string(), "clear".to_string()]),
+            Some(RankedClues { primary: "bright".to_string(), backups: vec!["clear".to_string()] })
+        );
+        assert_eq!(RankedClues::from_ranked(&[]), None);
+    }
+}
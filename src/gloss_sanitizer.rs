@@ -0,0 +1,222 @@
+// gloss_sanitizer.rs - Keep clues from giving the answer away
+//
+// Dictionary glosses often name the character they describe: "variant of 筏[fá]",
+// "(same as 屇)", "see 明白[míng bai]". Shown as a clue, that is the answer. Every clue is
+// checked for the target's forms (the character, the character it is a variant of, and its
+// simplified / traditional counterparts) and its readings, with tone marks or CC-CEDICT
+// tone numbers, and each occurrence is replaced with REDACTION_MARKER.
+//
+// A clue with nothing left but the marker, brackets and filler such as "variant of" is
+// dropped, as is a clue that repeats an earlier one once redacted.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::pinyin::{to_numbered, tone_of};
+
+/// Replaces the target and its readings in a clue; also marks the target in top-word clues
+pub const REDACTION_MARKER: &str = "_";
+
+/// Words that carry no meaning once the characters they refer to are redacted
+const FILLER_WORDS: [&str; 22] = [
+    "a", "abbr", "also", "an", "ancient", "archaic", "as", "erroneous", "for", "form", "in", "of",
+    "old", "or", "pr", "same", "see", "the", "used", "variant", "variants", "written",
+];
+
+/// What gives the answer away for one character or word
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Spoilers {
+    /// The target and its variant / counterpart forms
+    pub forms: Vec<String>,
+    /// Readings of the target, with tone marks
+    pub readings: Vec<String>,
+}
+
+impl Spoilers {
+    /// Whether any form of the target appears in `text`
+    pub fn appears_in(&self, text: &str) -> bool {
+        self.forms.iter().any(|form| !form.is_empty() && text.contains(form.as_str()))
+    }
+
+    /// `text` with every form of the target replaced by REDACTION_MARKER
+    pub fn redact_forms(&self, text: &str) -> String {
+        // Longest first, so a word is redacted whole before any character inside it
+        let mut forms: Vec<&String> = self.forms.iter().filter(|form| !form.is_empty()).collect();
+        forms.sort_by_key(|form| std::cmp::Reverse(form.chars().count()));
+        forms
+            .into_iter()
+            .fold(text.to_string(), |text, form| text.replace(form.as_str(), REDACTION_MARKER))
+    }
+
+    /// `text` with every form and reading of the target replaced by REDACTION_MARKER
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = self.redact_forms(text);
+        for reading in &self.readings {
+            // A toneless reading ("ma") cannot be told apart from English text
+            if reading.split_whitespace().all(|syllable| tone_of(syllable) == 5) {
+                continue;
+            }
+            let numbered = to_numbered(reading);
+            for spelling in [reading.clone(), numbered.replace('ü', "u:"), numbered.replace('ü', "v"), numbered] {
+                redacted = replace_phrase(&redacted, &spelling, REDACTION_MARKER);
+            }
+        }
+        redacted
+    }
+}
+
+/// Replace whole-word, case-insensitive occurrences of `phrase` in `text`
+fn replace_phrase(text: &str, phrase: &str, replacement: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let pattern: Vec<char> = phrase.chars().flat_map(char::to_lowercase).collect();
+    if pattern.is_empty() {
+        return text.to_string();
+    }
+    let is_word_char = |c: char| c.is_alphanumeric() || c == ':';
+
+    let mut result = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let matches = i + pattern.len() <= chars.len()
+            && chars[i..i + pattern.len()]
+                .iter()
+                .zip(&pattern)
+                .all(|(&c, &p)| c.to_lowercase().eq(std::iter::once(p)))
+            && (i == 0 || !is_word_char(chars[i - 1]))
+            && chars.get(i + pattern.len()).is_none_or(|&c| !is_word_char(c));
+        if matches {
+            result.push_str(replacement);
+            i += pattern.len();
+        } else {
+            result.push(chars[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+/// Whether a redacted clue still says something about the meaning
+pub fn is_useful(clue: &str) -> bool {
+    clue.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .any(|word| !FILLER_WORDS.contains(&word.as_str()) && !word.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// A clue that was changed by the sanitizer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Redaction {
+    pub original: String,
+    /// The clue as shown, absent if it was dropped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redacted: Option<String>,
+}
+
+/// What the sanitizer did to one gloss file, saved in gloss_redactions.json
+#[derive(Debug, Default, Serialize)]
+pub struct RedactionReport {
+    /// Clues checked
+    pub clues: usize,
+    /// Clues kept after redacting the target or its readings
+    pub redacted: usize,
+    /// Clues dropped because nothing useful was left, or they repeated an earlier clue
+    pub dropped: usize,
+    /// Entries whose every clue was dropped
+    pub without_clues: Vec<String>,
+    /// Entry → the clues that changed
+    pub changes: BTreeMap<String, Vec<Redaction>>,
+}
+
+impl RedactionReport {
+    /// Redact the clues of `entry`, recording every change; returns the clues to show
+    pub fn sanitize(&mut self, entry: &str, clues: Vec<String>, spoilers: &Spoilers) -> Vec<String> {
+        let had_clues = !clues.is_empty();
+        let mut seen = HashSet::new();
+        let mut kept = Vec::new();
+
+        for clue in clues {
+            self.clues += 1;
+            let redacted = spoilers.redact(&clue);
+            let keep = is_useful(&redacted) && seen.insert(redacted.clone());
+
+            if keep && redacted == clue {
+                kept.push(clue);
+                continue;
+            }
+            let shown = keep.then(|| redacted.clone());
+            if keep {
+                self.redacted += 1;
+                kept.push(redacted);
+            } else {
+                self.dropped += 1;
+            }
+            self.changes.entry(entry.to_string()).or_default().push(Redaction {
+                original: clue,
+                redacted: shown,
+            });
+        }
+
+        if had_clues && kept.is_empty() {
+            self.without_clues.push(entry.to_string());
+        }
+        kept
+    }
+
+    /// Sort the entries without clues, for stable output
+    pub fn finish(&mut self) {
+        self.without_clues.sort_unstable();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoilers(forms: &[&str], readings: &[&str]) -> Spoilers {
+        Spoilers {
+            forms: forms.iter().map(|f| f.to_string()).collect(),
+            readings: readings.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_variant_references_are_redacted_and_dropped() {
+        // 栰 is a variant of 筏
+        let spoilers = spoilers(&["栰", "筏"], &["fá"]);
+        assert_eq!(spoilers.redact("variant of 筏[fá]"), "variant of _[_]");
+        assert!(!is_useful("variant of _[_]"));
+        assert_eq!(spoilers.redact("raft; variant of 筏[fa2]"), "raft; variant of _[_]");
+        assert!(is_useful("raft; variant of _[_]"));
+    }
+
+    #[test]
+    fn test_readings_only_match_whole_words_with_a_tone() {
+        let ta = spoilers(&["他"], &["tā"]);
+        assert_eq!(ta.redact("he or him; Ta1 as a surname"), "he or him; _ as a surname");
+        assert_eq!(ta.redact("tale of a tā"), "tale of a _");
+        // A neutral-tone reading looks like an English word and is left alone
+        assert_eq!(Spoilers { readings: vec!["ma".to_string()], ..Spoilers::default() }.redact("ma'am"), "ma'am");
+        assert_eq!(spoilers(&[], &["lǜ"]).redact("green [lu:4]"), "green [_]");
+    }
+
+    #[test]
+    fn test_report_counts_redacted_and_dropped_clues() {
+        let mut report = RedactionReport::default();
+        let spoilers = spoilers(&["屆"], &["jiè"]);
+        let clues = vec![
+            "(same as 屆)".to_string(),
+            "to arrive at (place or time)".to_string(),
+            "period; session (jiè)".to_string(),
+            "period; session (_)".to_string(),
+        ];
+        let kept = report.sanitize("届", clues, &spoilers);
+
+        assert_eq!(kept, vec!["to arrive at (place or time)", "period; session (_)"]);
+        assert_eq!((report.clues, report.redacted, report.dropped), (4, 1, 2));
+        assert_eq!(report.changes["届"].len(), 3);
+        assert!(report.without_clues.is_empty());
+
+        assert!(report.sanitize("屇", vec!["old variant of 屆".to_string()], &spoilers).is_empty());
+        assert_eq!(report.without_clues, vec!["屇"]);
+    }
+}
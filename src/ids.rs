@@ -0,0 +1,431 @@
+// Ideographic Description Sequence (IDS) syntax tree
+//
+// An IDS such as ⿰日月 describes a character as an operator applied to its parts.
+// Parts are either plain characters, entity references (&CDP-8B7A;) for glyphs that
+// have no Unicode code point, or nested sequences (⿱⿰木木木 for 森).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Ideographic Description Characters (U+2FF0 to U+2FFF)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdsOperator {
+    /// ⿰ left to right
+    LeftToRight,
+    /// ⿱ above to below
+    AboveToBelow,
+    /// ⿲ left to middle and right
+    LeftToMiddleAndRight,
+    /// ⿳ above to middle and below
+    AboveToMiddleAndBelow,
+    /// ⿴ full surround
+    FullSurround,
+    /// ⿵ surround from above
+    SurroundFromAbove,
+    /// ⿶ surround from below
+    SurroundFromBelow,
+    /// ⿷ surround from left
+    SurroundFromLeft,
+    /// ⿸ surround from upper left
+    SurroundFromUpperLeft,
+    /// ⿹ surround from upper right
+    SurroundFromUpperRight,
+    /// ⿺ surround from lower left
+    SurroundFromLowerLeft,
+    /// ⿻ overlaid
+    Overlaid,
+    /// ⿼ surround from right
+    SurroundFromRight,
+    /// ⿽ surround from lower right
+    SurroundFromLowerRight,
+    /// ⿾ horizontal reflection
+    HorizontalReflection,
+    /// ⿿ rotation
+    Rotation,
+}
+
+/// Where a part sits inside its parent, derived from the operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Left,
+    Middle,
+    Right,
+    Top,
+    Bottom,
+    /// The enclosing part of a surround operator
+    Outer,
+    /// The enclosed part of a surround operator
+    Inner,
+    /// The first part of an overlay, or the only part of a reflection/rotation
+    Base,
+    /// The part drawn over the base
+    Overlay,
+}
+
+impl IdsOperator {
+    pub const ALL: [IdsOperator; 16] = [
+        IdsOperator::LeftToRight,
+        IdsOperator::AboveToBelow,
+        IdsOperator::LeftToMiddleAndRight,
+        IdsOperator::AboveToMiddleAndBelow,
+        IdsOperator::FullSurround,
+        IdsOperator::SurroundFromAbove,
+        IdsOperator::SurroundFromBelow,
+        IdsOperator::SurroundFromLeft,
+        IdsOperator::SurroundFromUpperLeft,
+        IdsOperator::SurroundFromUpperRight,
+        IdsOperator::SurroundFromLowerLeft,
+        IdsOperator::Overlaid,
+        IdsOperator::SurroundFromRight,
+        IdsOperator::SurroundFromLowerRight,
+        IdsOperator::HorizontalReflection,
+        IdsOperator::Rotation,
+    ];
+
+    pub fn from_char(c: char) -> Option<IdsOperator> {
+        let index = (c as u32).checked_sub(0x2FF0)?;
+        IdsOperator::ALL.get(index as usize).copied()
+    }
+
+    pub fn as_char(self) -> char {
+        let index = IdsOperator::ALL
+            .iter()
+            .position(|&op| op == self)
+            .unwrap_or(0);
+        char::from_u32(0x2FF0 + index as u32).unwrap_or('⿰')
+    }
+
+    /// Number of parts this operator takes
+    pub fn arity(self) -> usize {
+        self.positions().len()
+    }
+
+    /// Position of each part, in the order the parts are written
+    pub fn positions(self) -> &'static [Position] {
+        use Position::*;
+        match self {
+            IdsOperator::LeftToRight => &[Left, Right],
+            IdsOperator::AboveToBelow => &[Top, Bottom],
+            IdsOperator::LeftToMiddleAndRight => &[Left, Middle, Right],
+            IdsOperator::AboveToMiddleAndBelow => &[Top, Middle, Bottom],
+            IdsOperator::FullSurround
+            | IdsOperator::SurroundFromAbove
+            | IdsOperator::SurroundFromBelow
+            | IdsOperator::SurroundFromLeft
+            | IdsOperator::SurroundFromUpperLeft
+            | IdsOperator::SurroundFromUpperRight
+            | IdsOperator::SurroundFromLowerLeft
+            | IdsOperator::SurroundFromRight
+            | IdsOperator::SurroundFromLowerRight => &[Outer, Inner],
+            IdsOperator::Overlaid => &[Base, Overlay],
+            IdsOperator::HorizontalReflection | IdsOperator::Rotation => &[Base],
+        }
+    }
+}
+
+/// Parsed IDS
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Ids {
+    /// An operator applied to its parts (2 or 3, or 1 for reflection/rotation)
+    Compound {
+        operator: IdsOperator,
+        /// Extended IDC entity (&U-i001+2FF1;) the operator was written as, if any
+        variant: Option<String>,
+        children: Vec<Ids>,
+    },
+    /// A single Unicode character
+    Char(char),
+    /// An entity reference such as &CDP-8B7A;
+    Entity(String),
+}
+
+/// Why an IDS string could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdsParseError {
+    /// The input was empty
+    Empty,
+    /// The input ended while an operator still expected parts
+    MissingOperand { operator: char, expected: usize, found: usize },
+    /// An entity reference was opened with '&' but never closed with ';'
+    UnterminatedEntity { offset: usize },
+    /// A complete sequence was parsed but input remains
+    TrailingInput { offset: usize },
+}
+
+impl fmt::Display for IdsParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdsParseError::Empty => write!(f, "empty IDS"),
+            IdsParseError::MissingOperand { operator, expected, found } => write!(
+                f,
+                "operator {} expects {} parts but only {} were given",
+                operator, expected, found
+            ),
+            IdsParseError::UnterminatedEntity { offset } => {
+                write!(f, "unterminated entity reference at byte {}", offset)
+            }
+            IdsParseError::TrailingInput { offset } => {
+                write!(f, "unexpected trailing input at byte {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for IdsParseError {}
+
+impl Ids {
+    /// Parse an IDS string strictly: every operator must have exactly its arity in parts
+    /// and the whole input must be consumed
+    pub fn parse(input: &str) -> Result<Ids, IdsParseError> {
+        let mut parser = Parser { input, offset: 0 };
+        let ids = parser.parse_node()?.ok_or(IdsParseError::Empty)?;
+        if parser.offset < input.len() {
+            return Err(IdsParseError::TrailingInput { offset: parser.offset });
+        }
+        Ok(ids)
+    }
+
+    /// Leaf parts in written order, repeated parts included
+    pub fn leaves(&self) -> Vec<String> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(&self, leaves: &mut Vec<String>) {
+        match self {
+            Ids::Compound { children, .. } => {
+                for child in children {
+                    child.collect_leaves(leaves);
+                }
+            }
+            Ids::Char(c) => leaves.push(c.to_string()),
+            Ids::Entity(entity) => leaves.push(entity.clone()),
+        }
+    }
+
+    /// Copy of the tree with entity references replaced
+    /// `replace` is called for each entity; returning None keeps the entity as it is
+    pub fn map_entities(&self, replace: &mut impl FnMut(&str) -> Option<Ids>) -> Ids {
+        match self {
+            Ids::Compound { operator, variant, children } => Ids::Compound {
+                operator: *operator,
+                variant: variant.clone(),
+                children: children.iter().map(|child| child.map_entities(replace)).collect(),
+            },
+            Ids::Char(c) => Ids::Char(*c),
+            Ids::Entity(entity) => replace(entity).unwrap_or_else(|| self.clone()),
+        }
+    }
+
+    /// Copy of the tree with every operator written as its standard character, so the
+    /// extended IDC variants (&U-i001+2FF1;) do not reach displayed IDS strings
+    pub fn with_standard_operators(&self) -> Ids {
+        match self {
+            Ids::Compound { operator, children, .. } => Ids::Compound {
+                operator: *operator,
+                variant: None,
+                children: children.iter().map(Ids::with_standard_operators).collect(),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Leaf parts paired with the chain of positions leading to them
+    /// (e.g. 森 = ⿱木⿰木木 gives 木 at [Top], 木 at [Bottom, Left], 木 at [Bottom, Right])
+    pub fn positioned_leaves(&self) -> Vec<(String, Vec<Position>)> {
+        let mut leaves = Vec::new();
+        self.collect_positioned(&mut Vec::new(), &mut leaves);
+        leaves
+    }
+
+    fn collect_positioned(&self, path: &mut Vec<Position>, leaves: &mut Vec<(String, Vec<Position>)>) {
+        match self {
+            Ids::Compound { operator, children, .. } => {
+                for (child, &position) in children.iter().zip(operator.positions()) {
+                    path.push(position);
+                    child.collect_positioned(path, leaves);
+                    path.pop();
+                }
+            }
+            Ids::Char(c) => leaves.push((c.to_string(), path.clone())),
+            Ids::Entity(entity) => leaves.push((entity.clone(), path.clone())),
+        }
+    }
+}
+
+impl FromStr for Ids {
+    type Err = IdsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ids::parse(s)
+    }
+}
+
+impl fmt::Display for Ids {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ids::Compound { operator, variant, children } => {
+                match variant {
+                    Some(entity) => write!(f, "{}", entity)?,
+                    None => write!(f, "{}", operator.as_char())?,
+                }
+                for child in children {
+                    write!(f, "{}", child)?;
+                }
+                Ok(())
+            }
+            Ids::Char(c) => write!(f, "{}", c),
+            Ids::Entity(entity) => write!(f, "{}", entity),
+        }
+    }
+}
+
+/// Extended IDC entity references (&U-i001+2FF1;) name a glyph variant of an operator
+pub fn extended_idc_operator(entity: &str) -> Option<IdsOperator> {
+    let rest = entity.strip_prefix("&U-i")?.strip_suffix(';')?;
+    let (_, hex) = rest.split_once('+')?;
+    let code = u32::from_str_radix(hex, 16).ok()?;
+    IdsOperator::from_char(char::from_u32(code)?)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+enum Token {
+    Operator(IdsOperator, Option<String>),
+    Char(char),
+    Entity(String),
+}
+
+impl Parser<'_> {
+    fn next_token(&mut self) -> Result<Option<Token>, IdsParseError> {
+        let rest = &self.input[self.offset..];
+        let Some(c) = rest.chars().next() else {
+            return Ok(None);
+        };
+
+        if c == '&' {
+            let end = rest
+                .find(';')
+                .ok_or(IdsParseError::UnterminatedEntity { offset: self.offset })?;
+            let entity = &rest[..=end];
+            self.offset += end + 1;
+            return Ok(Some(match extended_idc_operator(entity) {
+                Some(operator) => Token::Operator(operator, Some(entity.to_string())),
+                None => Token::Entity(entity.to_string()),
+            }));
+        }
+
+        self.offset += c.len_utf8();
+        Ok(Some(match IdsOperator::from_char(c) {
+            Some(operator) => Token::Operator(operator, None),
+            None => Token::Char(c),
+        }))
+    }
+
+    fn parse_node(&mut self) -> Result<Option<Ids>, IdsParseError> {
+        let node = match self.next_token()? {
+            None => return Ok(None),
+            Some(Token::Char(c)) => Ids::Char(c),
+            Some(Token::Entity(entity)) => Ids::Entity(entity),
+            Some(Token::Operator(operator, variant)) => {
+                let expected = operator.arity();
+                let mut children = Vec::with_capacity(expected);
+                while children.len() < expected {
+                    match self.parse_node()? {
+                        Some(child) => children.push(child),
+                        None => {
+                            return Err(IdsParseError::MissingOperand {
+                                operator: operator.as_char(),
+                                expected,
+                                found: children.len(),
+                            })
+                        }
+                    }
+                }
+                Ids::Compound { operator, variant, children }
+            }
+        };
+        Ok(Some(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_simple_compound() {
+        let ids = Ids::parse("⿰日月").unwrap();
+        assert_eq!(
+            ids,
+            Ids::Compound {
+                operator: IdsOperator::LeftToRight,
+                variant: None,
+                children: vec![Ids::Char('日'), Ids::Char('月')],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_and_ternary() {
+        let ids = Ids::parse("⿲辛⿱一力辛").unwrap();
+        let Ids::Compound { operator, children, .. } = &ids else {
+            panic!("expected compound");
+        };
+        assert_eq!(*operator, IdsOperator::LeftToMiddleAndRight);
+        assert_eq!(children.len(), 3);
+        assert_eq!(ids.leaves(), vec!["辛", "一", "力", "辛"]);
+    }
+
+    #[test]
+    fn test_parse_entities_and_extended_idc() {
+        let ids = Ids::parse("&U-i001+2FF1;亣&CDP-8B7A;").unwrap();
+        let Ids::Compound { operator, variant, children } = &ids else {
+            panic!("expected compound");
+        };
+        assert_eq!(*operator, IdsOperator::AboveToBelow);
+        assert_eq!(variant.as_deref(), Some("&U-i001+2FF1;"));
+        assert_eq!(children[1], Ids::Entity("&CDP-8B7A;".to_string()));
+
+        // A &U-i…; entity outside the operator range is an ordinary component
+        assert_eq!(Ids::parse("&U-i001+20541;").unwrap(), Ids::Entity("&U-i001+20541;".to_string()));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        assert_eq!(Ids::parse(""), Err(IdsParseError::Empty));
+        assert_eq!(
+            Ids::parse("⿰日"),
+            Err(IdsParseError::MissingOperand { operator: '⿰', expected: 2, found: 1 })
+        );
+        assert_eq!(
+            Ids::parse("⿳一口"),
+            Err(IdsParseError::MissingOperand { operator: '⿳', expected: 3, found: 2 })
+        );
+        assert_eq!(Ids::parse("⿰日月木"), Err(IdsParseError::TrailingInput { offset: 9 }));
+        assert_eq!(Ids::parse("⿰&CDP-8B7A"), Err(IdsParseError::UnterminatedEntity { offset: 3 }));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for input in ["⿰日月", "⿱⿰木木木", "⿲辛⿱一力辛", "&U-i001+2FF1;亣八", "⿰&CDP-8B7A;攵", "口"] {
+            assert_eq!(Ids::parse(input).unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let ids = Ids::parse("⿱木⿰木木").unwrap();
+        let positioned = ids.positioned_leaves();
+        assert_eq!(positioned[0].1, vec![Position::Top]);
+        assert_eq!(positioned[1].1, vec![Position::Bottom, Position::Left]);
+        assert_eq!(positioned[2].1, vec![Position::Bottom, Position::Right]);
+        assert_eq!(IdsOperator::from_char('⿴').unwrap().positions(), &[Position::Outer, Position::Inner]);
+    }
+}
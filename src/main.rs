@@ -1,74 +1,702 @@
-// Simplified main.rs for HSK level analysis and game data generation
+// main.rs - CLI for HSK level analysis and game data generation
 mod chinese_types;
 mod chinese_char_types;
+mod component_key;
+mod component_roles;
+mod curriculum;
+mod entities;
+mod frequency;
 mod game_data_builder;
+mod gloss_ranking;
+mod gloss_sanitizer;
+mod ids;
+mod phonetic_series;
+mod pinyin;
+mod pinyin_rounds;
+mod puzzle;
+mod radicals;
+mod script;
+mod solver;
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
-use std::fs::File;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader, Write};
+use std::num::NonZeroUsize;
 
 use chinese_types::ChineseDictionaryElement;
 use chinese_char_types::ChineseCharacter;
-use game_data_builder::{load_all_ids, build_game_data, save_game_data};
+use component_key::{component_key, parse_component_key, KEY_SEPARATOR};
+use component_roles::{ComponentAnnotation, ComponentRole};
+use curriculum::character_levels;
+use entities::{load_entity_table, resolve_entities};
+use frequency::{build_frequency_levels, CorpusRanks, FrequencyEntry};
+use gloss_ranking::{rank_glosses, reading_shares, GlossCandidate, RankedClues, ReadingShares};
+use gloss_sanitizer::{RedactionReport, Spoilers};
+use ids::Ids;
+use pinyin::{distinct_readings, readings_by_frequency, Reading};
+use pinyin_rounds::{build_pinyin_rounds, PinyinRoundLevel, MAX_HOMOPHONES};
+use puzzle::{generate_puzzles, save_puzzles, PuzzleConfig};
+use radicals::KANGXI_RADICALS;
+use script::{Script, ScriptMode, ScriptVariants};
+use solver::{solve, Solution, MAX_STATES};
+use game_data_builder::{
+    build_char_decompositions, build_components_to_chars, build_game_data,
+    decompose_to_leaf_counts, extract_components_from_ids, leaves_of, load_all_ids, load_ids_sources, save_game_data,
+    select_ids, CharacterFacts, Granularity, IdsFile, IdsPolicy,
+};
+
+/// Chinese character composition game - data generator
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(flatten)]
+    inputs: InputArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Input and output locations shared by all subcommands
+#[derive(Debug, Args)]
+struct InputArgs {
+    /// Word dictionary (JSONL)
+    #[arg(long, global = true, default_value = "chinese_dictionary_word_2025-06-25.jsonl")]
+    words: String,
+
+    /// Character dictionary (JSONL)
+    #[arg(long, global = true, default_value = "chinese_dictionary_char_2025-06-25.jsonl")]
+    chars: String,
+
+    /// IDS files to load, in order (repeat the flag or pass a comma-separated list;
+    /// `default` stands for every CHISE UCS file found in ids/, then CDP and JIS X 0208,
+    /// which is also what is loaded without the flag)
+    #[arg(long, global = true, value_delimiter = ',')]
+    ids: Vec<String>,
+
+    /// Which script to build the game in; `both` writes simp/ and trad/ side by side
+    #[arg(long, global = true, value_enum, default_value_t = ScriptMode::default())]
+    script: ScriptMode,
+
+    /// Region tags to prefer when a character has several IDS descriptions, in order
+    /// (default: G for simp, T,H for trad)
+    #[arg(long, global = true, value_delimiter = ',')]
+    ids_regions: Vec<char>,
+
+    /// Prefer @apparent= (visible shape) IDS descriptions over etymological ones
+    #[arg(long, global = true)]
+    ids_apparent: bool,
+
+    /// Hand-maintained entity reference → Unicode mapping table
+    #[arg(long, global = true, default_value = "ids/entity_map.txt")]
+    entity_map: String,
+
+    /// Directory the generated game data is written to
+    #[arg(long, global = true, default_value = "game_data")]
+    out: String,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print HSK level statistics for the word and character dictionaries
+    Analyze,
+    /// Run the full pipeline and write all game data files
+    Build {
+        #[command(flatten)]
+        granularity: GranularityArgs,
+
+        #[command(flatten)]
+        puzzles: PuzzleArgs,
+    },
+    /// Show how a character decomposes according to the IDS data
+    Decompose {
+        /// Character (or entity reference like &CDP-8B7A;) to decompose
+        character: String,
+
+        #[command(flatten)]
+        granularity: GranularityArgs,
+    },
+    /// Find characters that can be formed from a set of components
+    Lookup {
+        /// Components in any order, e.g. 日月 or 日|月
+        components: String,
+
+        #[command(flatten)]
+        granularity: GranularityArgs,
+    },
+    /// Find the shortest way to build a character two or three cards at a time
+    Solve {
+        /// Character to build
+        character: String,
+        /// Cards to start from, e.g. 木士豆寸 or 木|士|豆|寸 (default: its leaf components)
+        cards: Option<String>,
+
+        #[command(flatten)]
+        granularity: GranularityArgs,
+    },
+    /// Extract word and character glosses only
+    Glosses,
+    /// Show the simplified / traditional counterparts of a character
+    Variants {
+        /// Character to look up
+        character: String,
+        /// A character the player built, checked against CHARACTER
+        built: Option<String>,
+    },
+    /// Show the words of one HSK level in the generated game data
+    Hsk {
+        /// HSK level (1-7)
+        level: u8,
+    },
+    /// Show how much the generated game data contains
+    Stats,
+    /// List the leaf components of the generated game data
+    Components,
+}
+
+/// Kinds of components that decomposition stops at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StopAt {
+    /// The 214 Kangxi radicals
+    Radicals,
+    /// Characters that are themselves part of an HSK word
+    Hsk,
+    /// Characters with a gloss in the character dictionary
+    Glossed,
+}
+
+/// How far characters are broken down into leaf components
+#[derive(Debug, Args)]
+struct GranularityArgs {
+    /// Keep these components whole instead of breaking them into fragments like 丿 or 丶
+    /// (comma-separated; default: decompose all the way down)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    stop_at: Vec<StopAt>,
+
+    /// Keep components this many levels below a character whole (at least 1: a character
+    /// is always decomposed)
+    #[arg(long)]
+    max_depth: Option<NonZeroUsize>,
+}
+
+impl GranularityArgs {
+    fn granularity(&self, hsk_words: &HashMap<u8, Vec<String>>, chinese_chars: &[ChineseCharacter]) -> Granularity {
+        let mut stops = HashSet::new();
+        for stop_at in &self.stop_at {
+            match stop_at {
+                StopAt::Radicals => {
+                    stops.extend(KANGXI_RADICALS.iter().map(|c| c.to_string()));
+                }
+                StopAt::Hsk => {
+                    stops.extend(hsk_words.values().flatten().flat_map(|w| w.chars()).map(String::from));
+                }
+                StopAt::Glossed => {
+                    stops.extend(
+                        chinese_chars
+                            .iter()
+                            .filter(|c| c.gloss.as_ref().is_some_and(|g| !g.trim().is_empty()))
+                            .map(|c| c.char.clone()),
+                    );
+                }
+            }
+        }
+        Granularity {
+            stops,
+            max_depth: self.max_depth,
+        }
+    }
+
+    /// The granularity for commands that inspect the IDS data directly; the dictionaries
+    /// are only loaded when a stop needs them
+    fn load_granularity(&self, inputs: &InputArgs) -> Result<Granularity> {
+        if self.stop_at.iter().all(|&stop_at| stop_at == StopAt::Radicals) {
+            return Ok(self.granularity(&HashMap::new(), &[]));
+        }
+        let (chinese_words, chinese_chars) = load_dictionaries(inputs)?;
+        let hsk_words = extract_hsk_words(&chinese_words, inputs.script.primary());
+        Ok(self.granularity(&hsk_words, &chinese_chars))
+    }
+}
+
+/// Options for the pre-baked puzzles_<level>.json rounds
+#[derive(Debug, Args)]
+struct PuzzleArgs {
+    /// Seed for round generation; the same seed always gives the same rounds
+    #[arg(long, default_value_t = PuzzleConfig::default().seed)]
+    seed: u64,
+
+    /// Decoy characters whose components are mixed into each round
+    #[arg(long, default_value_t = PuzzleConfig::default().decoys)]
+    decoys: usize,
+
+    /// Rounds generated per HSK level
+    #[arg(long, default_value_t = PuzzleConfig::default().rounds_per_level)]
+    rounds: usize,
+}
+
+impl PuzzleArgs {
+    fn config(&self) -> PuzzleConfig {
+        PuzzleConfig {
+            seed: self.seed,
+            decoys: self.decoys,
+            rounds_per_level: self.rounds,
+        }
+    }
+}
+
+impl InputArgs {
+    fn ids_policy(&self, script: Script) -> IdsPolicy {
+        let preferred_regions = if self.ids_regions.is_empty() {
+            script.default_ids_regions()
+        } else {
+            self.ids_regions.clone()
+        };
+        IdsPolicy {
+            preferred_regions,
+            use_apparent: self.ids_apparent,
+        }
+    }
+
+    /// The IDS files to load
+    /// Files named with --ids are required; missing default files are skipped
+    fn ids_files(&self) -> Vec<IdsFile> {
+        if self.ids.is_empty() {
+            return default_ids_files();
+        }
+        self.ids
+            .iter()
+            .flat_map(|path| match path.as_str() {
+                DEFAULT_IDS => default_ids_files(),
+                _ => vec![IdsFile { path: path.clone(), required: true }],
+            })
+            .collect()
+    }
+
+    fn entity_table(&self) -> Result<HashMap<String, String>> {
+        load_entity_table(&self.entity_map)
+            .with_context(|| format!("Failed to load entity mapping table {}", self.entity_map))
+    }
+
+    /// Load the IDS files, pick one description per character and resolve entity references
+    fn load_ids_map(&self) -> Result<HashMap<String, String>> {
+        let alternatives = load_all_ids(&self.ids_files())
+            .context("Failed to load IDS data")?;
+        let ids_map = select_ids(&alternatives, &self.ids_policy(self.script.primary()));
+        let (ids_map, _) = resolve_entities(&ids_map, &self.entity_table()?);
+        Ok(ids_map)
+    }
+}
+
+/// `--ids` entry that stands for the default files
+const DEFAULT_IDS: &str = "default";
+
+/// Every CHISE UCS file plus CDP and JIS definitions; files that have not been
+/// downloaded into ids/ are skipped
+fn default_ids_files() -> Vec<IdsFile> {
+    [
+        "ids/IDS-UCS-Basic.txt",
+        "ids/IDS-UCS-Ext-A.txt",
+        "ids/IDS-UCS-Ext-B-1.txt",
+        "ids/IDS-UCS-Ext-B-2.txt",
+        "ids/IDS-UCS-Ext-B-3.txt",
+        "ids/IDS-UCS-Ext-B-4.txt",
+        "ids/IDS-UCS-Ext-B-5.txt",
+        "ids/IDS-UCS-Ext-B-6.txt",
+        "ids/IDS-UCS-Ext-C.txt",
+        "ids/IDS-UCS-Ext-D.txt",
+        "ids/IDS-UCS-Ext-E.txt",
+        "ids/IDS-UCS-Ext-F.txt",
+        "ids/IDS-UCS-Ext-G.txt",
+        "ids/IDS-CDP.txt", // CDP entity references
+        "ids/IDS-JIS-X0208-1990.txt", // JIS X 0208 glyphs, keyed by their Unicode character
+    ]
+    .iter()
+    .map(|path| IdsFile { path: path.to_string(), required: false })
+    .collect()
+}
 
 fn main() -> Result<()> {
-    println!("🚀 Starting HSK level analysis and game data generation...");
+    let cli = Cli::parse();
 
+    match &cli.command {
+        Command::Analyze => run_analyze(&cli.inputs),
+        Command::Build { granularity, puzzles } => run_build(&cli.inputs, granularity, &puzzles.config()),
+        Command::Decompose { character, granularity } => run_decompose(&cli.inputs, character, granularity),
+        Command::Lookup { components, granularity } => run_lookup(&cli.inputs, components, granularity),
+        Command::Solve { character, cards, granularity } => {
+            run_solve(&cli.inputs, character, cards.as_deref(), granularity)
+        }
+        Command::Glosses => run_glosses(&cli.inputs),
+        Command::Variants { character, built } => run_variants(&cli.inputs, character, built.as_deref()),
+        Command::Hsk { level } => run_hsk(&cli.inputs, *level),
+        Command::Stats => run_stats(&cli.inputs),
+        Command::Components => run_components(&cli.inputs),
+    }
+}
+
+fn load_dictionaries(
+    inputs: &InputArgs,
+) -> Result<(Vec<ChineseDictionaryElement>, Vec<ChineseCharacter>)> {
     println!("📚 Loading Chinese word dictionary...");
-    let chinese_words = load_chinese_dictionary("chinese_dictionary_word_2025-06-25.jsonl")
-        .context("Failed to load Chinese word dictionary")?;
+    let chinese_words = load_chinese_dictionary(&inputs.words)
+        .with_context(|| format!("Failed to load Chinese word dictionary {}", inputs.words))?;
 
     println!("📚 Loading Chinese character dictionary...");
-    let chinese_chars = load_chinese_char_dictionary("chinese_dictionary_char_2025-06-25.jsonl")
-        .context("Failed to load Chinese character dictionary")?;
+    let chinese_chars = load_chinese_char_dictionary(&inputs.chars)
+        .with_context(|| format!("Failed to load Chinese character dictionary {}", inputs.chars))?;
 
-    // Analyze HSK levels
+    Ok((chinese_words, chinese_chars))
+}
+
+fn run_analyze(inputs: &InputArgs) -> Result<()> {
+    println!("🚀 Starting HSK level analysis...");
+
+    let (chinese_words, chinese_chars) = load_dictionaries(inputs)?;
     analyze_hsk_levels(&chinese_words, &chinese_chars);
 
-    // Extract HSK words by level
-    println!("\n🎮 Extracting HSK words for game data...");
-    let hsk_words = extract_hsk_words(&chinese_words);
+    Ok(())
+}
 
-    // Extract word glosses
-    println!("\n📖 Extracting word glosses...");
-    let word_glosses = extract_word_glosses(&chinese_words);
-    println!("  ✅ Extracted {} word glosses", word_glosses.len());
+fn run_build(inputs: &InputArgs, granularity_args: &GranularityArgs, puzzle_config: &PuzzleConfig) -> Result<()> {
+    println!("🚀 Starting game data generation...");
 
-    // Extract character glosses with top words
-    println!("\n📖 Extracting character glosses with top words...");
-    let char_glosses = extract_char_glosses_with_top_words(&chinese_chars);
-    println!("  ✅ Extracted {} character glosses", char_glosses.len());
+    let (chinese_words, chinese_chars) = load_dictionaries(inputs)?;
 
     // Load IDS data
     println!("\n📖 Loading IDS (character decomposition) data...");
-    let ids_map = load_all_ids()
+    let ids_sources = load_ids_sources(&inputs.ids_files())
         .context("Failed to load IDS data")?;
+    let entity_table = inputs.entity_table()?;
+    let character_facts = CharacterFacts {
+        stroke_counts: extract_stroke_counts(&chinese_chars),
+        component_annotations: extract_component_annotations(&chinese_chars),
+        pinyin: extract_char_pinyin(&chinese_chars),
+    };
+    let mut hsk_characters = HashSet::new();
+
+    for script in inputs.script.scripts() {
+        let output_dir = inputs.script.output_dir(&inputs.out, script);
+        println!("\n🈶 {} game data → {}/", script.label(), output_dir);
+
+        // Extract HSK words by level
+        println!("\n🎮 Extracting HSK words for game data...");
+        let hsk_words = extract_hsk_words(&chinese_words, script);
+        hsk_characters.extend(hsk_words.values().flatten().flat_map(|word| word.chars()).map(String::from));
+        let granularity = granularity_args.granularity(&hsk_words, &chinese_chars);
+
+        // Build game data
+        println!("\n🎮 Building game data structures...");
+        let game_data = build_game_data(
+            hsk_words,
+            ids_sources.clone(),
+            &inputs.ids_policy(script),
+            &entity_table,
+            &character_facts,
+            &granularity,
+        );
+
+        // Save game data
+        println!("\n💾 Saving game data...");
+        save_game_data(&game_data, &output_dir)
+            .context("Failed to save game data")?;
+
+        // Pre-bake reproducible rounds
+        println!("\n🎲 Generating puzzles (seed {}, {} decoys)...", puzzle_config.seed, puzzle_config.decoys);
+        let puzzle_sets = generate_puzzles(
+            &game_data.hsk_words,
+            &game_data.char_to_decomposition,
+            &game_data.components_to_chars,
+            &game_data.solvability,
+            puzzle_config,
+        );
+        save_puzzles(&puzzle_sets, &output_dir)
+            .context("Failed to save puzzles")?;
+
+        write_glosses(&chinese_words, &chinese_chars, script, &output_dir)?;
+        write_pinyin(&chinese_words, &chinese_chars, &game_data.hsk_words, script, &output_dir)?;
+        write_frequency(&chinese_words, &chinese_chars, &game_data.hsk_words, script, &output_dir)?;
+    }
+
+    // Covers the characters of both scripts, so the client can accept either form; each
+    // script directory gets a copy next to the rest of its data
+    println!("\n🔀 Extracting simplified ↔ traditional counterparts...");
+    let script_variants = ScriptVariants::from_dictionary(&chinese_chars, &hsk_characters);
+    println!("  ✅ {} HSK characters have counterparts", script_variants.len());
+    for script in inputs.script.scripts() {
+        let output_dir = inputs.script.output_dir(&inputs.out, script);
+        save_script_variants(&script_variants, &format!("{}/script_variants.json", output_dir))
+            .context("Failed to save script variants")?;
+    }
+
+    println!("\n✅ All done! Game data saved to {}/ directory", inputs.out);
+
+    Ok(())
+}
+
+fn run_decompose(inputs: &InputArgs, character: &str, granularity_args: &GranularityArgs) -> Result<()> {
+    let granularity = granularity_args.load_granularity(inputs)?;
+    let alternatives = load_all_ids(&inputs.ids_files())
+        .context("Failed to load IDS data")?;
+    let ids_map = select_ids(&alternatives, &inputs.ids_policy(inputs.script.primary()));
+    let (ids_map, entity_report) = resolve_entities(&ids_map, &inputs.entity_table()?);
+    if let Some(mapping) = entity_report.mappings.get(character) {
+        println!("\n🔗 '{}' resolves to {} ({:?})", character, mapping.resolved, mapping.method);
+    }
+
+    if let Some(alternatives) = alternatives.get(character).filter(|a| a.len() > 1) {
+        println!("\n📚 {} IDS descriptions for '{}':", alternatives.len(), character);
+        for alternative in alternatives {
+            let mut tags = vec![alternative.source.clone()];
+            if !alternative.regions.is_empty() {
+                tags.push(format!("[{}]", alternative.regions));
+            }
+            if alternative.apparent {
+                tags.push("apparent".to_string());
+            }
+            println!("  {} ({})", alternative.ids, tags.join(", "));
+        }
+    }
+
+    println!("\n🔍 Decomposition of '{}':", character);
+    match ids_map.get(character) {
+        Some(ids) => {
+            println!("  IDS: {}", ids);
+            println!("  Components: {}", extract_components_from_ids(ids).join(" "));
+            match Ids::parse(ids) {
+                Ok(tree) => {
+                    let layout: Vec<String> = tree
+                        .positioned_leaves()
+                        .iter()
+                        .map(|(part, positions)| format!("{} {:?}", part, positions))
+                        .collect();
+                    println!("  Layout: {}", layout.join(", "));
+                }
+                Err(e) => println!("  ⚠️  Malformed IDS: {}", e),
+            }
+
+            let leaves: Vec<String> = decompose_to_leaf_counts(character, &ids_map, &granularity)
+                .iter()
+                .map(|(leaf, count)| format!("{}×{}", leaf, count))
+                .collect();
+            println!("  Leaves: {}", leaves.join(" "));
+        }
+        // Entity keys are dropped once resolved; they are never cards of their own
+        None if entity_report.unresolved.contains_key(character) => {
+            println!("  Unresolved - characters using it are kept whole");
+        }
+        None if character.starts_with('&') => {
+            println!("  Not a component - entity references are replaced wherever they are used");
+        }
+        None => println!("  No IDS entry - '{}' is a leaf component", character),
+    }
+
+    Ok(())
+}
+
+fn run_lookup(inputs: &InputArgs, components: &str, granularity_args: &GranularityArgs) -> Result<()> {
+    let granularity = granularity_args.load_granularity(inputs)?;
+    let ids_map = inputs.load_ids_map()?;
+    let decompositions = build_char_decompositions(&ids_map, &granularity);
+    let components_to_chars = build_components_to_chars(&decompositions);
+
+    // Accept either a canonical key (日|月) or components written together (日月);
+    // the latter is scanned like an IDS so entity references stay a single component
+    let parts = if components.contains(KEY_SEPARATOR) {
+        parse_component_key(components)
+    } else {
+        extract_components_from_ids(components)
+    };
+    let key = component_key(&parts);
+
+    println!("\n🔍 Characters that can be formed from '{}' (key {}):", components, key);
+    match components_to_chars.get(&key) {
+        Some(characters) => println!("  {}", characters.join(" ")),
+        None => println!("  (none)"),
+    }
+
+    Ok(())
+}
+
+fn run_solve(
+    inputs: &InputArgs,
+    character: &str,
+    cards: Option<&str>,
+    granularity_args: &GranularityArgs,
+) -> Result<()> {
+    let granularity = granularity_args.load_granularity(inputs)?;
+    let ids_map = inputs.load_ids_map()?;
+    let decompositions = build_char_decompositions(&ids_map, &granularity);
+    let components_to_chars = build_components_to_chars(&decompositions);
+
+    let cards = match cards {
+        Some(cards) if cards.contains(KEY_SEPARATOR) => parse_component_key(cards),
+        Some(cards) => extract_components_from_ids(cards),
+        None => leaves_of(character, &decompositions),
+    };
+
+    println!("\n🧩 Building '{}' from {}:", character, cards.join(" "));
+    match solve(&cards, character, &components_to_chars, &decompositions) {
+        Solution::Solved(steps) => {
+            for (index, step) in steps.iter().enumerate() {
+                println!("  {}. {} → {}", index + 1, step.inputs.join(" + "), step.output);
+            }
+            println!("  ✅ {} steps", steps.len());
+        }
+        Solution::Unsolvable => println!("  ❌ Cannot be built two or three cards at a time"),
+        Solution::TooComplex => println!("  ⚠️  Gave up after {} hands", MAX_STATES),
+    }
+
+    Ok(())
+}
+
+fn run_glosses(inputs: &InputArgs) -> Result<()> {
+    let (chinese_words, chinese_chars) = load_dictionaries(inputs)?;
+
+    for script in inputs.script.scripts() {
+        let output_dir = inputs.script.output_dir(&inputs.out, script);
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create output directory {}", output_dir))?;
+        write_glosses(&chinese_words, &chinese_chars, script, &output_dir)?;
+    }
+
+    Ok(())
+}
+
+fn run_variants(inputs: &InputArgs, character: &str, built: Option<&str>) -> Result<()> {
+    println!("📚 Loading Chinese character dictionary...");
+    let chinese_chars = load_chinese_char_dictionary(&inputs.chars)
+        .with_context(|| format!("Failed to load Chinese character dictionary {}", inputs.chars))?;
+
+    let mut keep: HashSet<String> = HashSet::from([character.to_string()]);
+    keep.extend(built.map(String::from));
+    let script_variants = ScriptVariants::from_dictionary(&chinese_chars, &keep);
+
+    println!("\n🔀 Counterparts of '{}':", character);
+    for script in [Script::Simp, Script::Trad] {
+        println!("  {}: {}", script.label(), script_variants.forms_in(character, script).join(" "));
+    }
+    if let Some(variant_of) = script_variants.get(character).and_then(|v| v.variant_of.as_ref()) {
+        println!("  Variant of: {}", variant_of);
+    }
 
-    // Build game data
-    println!("\n🎮 Building game data structures...");
-    let game_data = build_game_data(hsk_words, ids_map);
+    if let Some(built) = built {
+        match script_variants.match_build(character, built) {
+            Some(found) => println!("\n✅ '{}' is accepted for '{}' ({:?})", built, character, found),
+            None => println!("\n❌ '{}' is not a form of '{}'", built, character),
+        }
+    }
+
+    Ok(())
+}
 
-    // Save game data
-    println!("\n💾 Saving game data...");
-    save_game_data(&game_data, "game_data")
-        .context("Failed to save game data")?;
+/// Read one file of the game data written by `build` (the primary script's with --script both)
+fn load_game_data_file<T: DeserializeOwned>(inputs: &InputArgs, file_name: &str) -> Result<T> {
+    let output_dir = inputs.script.output_dir(&inputs.out, inputs.script.primary());
+    let path = format!("{}/{}", output_dir, file_name);
+    let file = File::open(&path).with_context(|| format!("Failed to open {} (run `build` first)", path))?;
+    serde_json::from_reader(BufReader::new(file)).with_context(|| format!("Failed to parse {}", path))
+}
+
+fn run_hsk(inputs: &InputArgs, level: u8) -> Result<()> {
+    let hsk_words: BTreeMap<u8, Vec<String>> = load_game_data_file(inputs, "hsk_words.json")?;
+    let words = hsk_words.get(&level).map(Vec::as_slice).unwrap_or_default();
+
+    println!("\n📚 HSK {} words (first 50):", level);
+    if words.is_empty() {
+        println!("  (none)");
+    } else {
+        println!("  {}", words.iter().take(50).cloned().collect::<Vec<_>>().join(" "));
+    }
+    println!("  Total: {} words", words.len());
+
+    Ok(())
+}
+
+fn run_stats(inputs: &InputArgs) -> Result<()> {
+    let allowed_components: Vec<String> = load_game_data_file(inputs, "allowed_components.json")?;
+    let char_to_decomposition: HashMap<String, serde_json::Value> =
+        load_game_data_file(inputs, "char_to_decomposition.json")?;
+    let components_to_chars: HashMap<String, Vec<String>> = load_game_data_file(inputs, "components_to_chars.json")?;
+    let hsk_words: BTreeMap<u8, Vec<String>> = load_game_data_file(inputs, "hsk_words.json")?;
+
+    println!("\n📊 Game data statistics:");
+    println!("  Allowed components: {}", allowed_components.len());
+    println!("  Character decompositions: {}", char_to_decomposition.len());
+    println!("  Component combinations: {}", components_to_chars.len());
+    println!("\n📚 HSK words by level:");
+    for (level, words) in &hsk_words {
+        println!("  HSK {}: {} words", level, words.len());
+    }
+
+    Ok(())
+}
+
+fn run_components(inputs: &InputArgs) -> Result<()> {
+    let counts: BTreeMap<String, usize> = load_game_data_file(inputs, "allowed_component_counts.json")?;
+
+    // Components a single character needs more than once show how many copies
+    let components: Vec<String> = counts
+        .iter()
+        .map(|(component, &count)| match count {
+            1 => component.clone(),
+            _ => format!("{}×{}", component, count),
+        })
+        .collect();
+
+    println!("\n🧩 Allowed components ({} total):", components.len());
+    for row in components.chunks(20) {
+        println!("  {}", row.join(" "));
+    }
+
+    Ok(())
+}
+
+fn write_glosses(
+    chinese_words: &[ChineseDictionaryElement],
+    chinese_chars: &[ChineseCharacter],
+    script: Script,
+    output_dir: &str,
+) -> Result<()> {
+    // Extract word glosses
+    println!("\n📖 Extracting {} word glosses...", script.label());
+    let mut redactions = GlossRedactions::default();
+    let reading_shares = extract_reading_shares(chinese_chars);
+    let word_glosses = extract_word_glosses(chinese_words, script, &reading_shares, &mut redactions.words);
+    println!("  ✅ Extracted {} word glosses, best clue first", word_glosses.len());
+    print_redactions(&redactions.words);
+    let word_clues: BTreeMap<&String, RankedClues> = word_glosses
+        .iter()
+        .filter_map(|(word, glosses)| Some((word, RankedClues::from_ranked(glosses)?)))
+        .collect();
+
+    // Extract character glosses with top words
+    println!("\n📖 Extracting character glosses with top words...");
+    let char_glosses = extract_char_glosses_with_top_words(chinese_chars, script, &mut redactions.characters);
+    println!("  ✅ Extracted {} character glosses", char_glosses.len());
+    print_redactions(&redactions.characters);
 
     // Save word glosses
-    save_word_glosses(&word_glosses, "game_data/word_glosses.json")
+    save_word_glosses(&word_glosses, &format!("{}/word_glosses.json", output_dir))
         .context("Failed to save word glosses")?;
 
+    // Save primary and backup clues
+    save_word_clues(&word_clues, &format!("{}/word_clues.json", output_dir))
+        .context("Failed to save word clues")?;
+
     // Save character glosses
-    save_word_glosses(&char_glosses, "game_data/char_glosses.json")
+    save_word_glosses(&char_glosses, &format!("{}/char_glosses.json", output_dir))
         .context("Failed to save character glosses")?;
 
-    println!("\n✅ All done! Game data saved to game_data/ directory");
+    // Save what the sanitizer redacted
+    save_gloss_redactions(&redactions, &format!("{}/gloss_redactions.json", output_dir))
+        .context("Failed to save gloss redaction report")?;
 
     Ok(())
 }
 
-
-
 fn load_chinese_dictionary(path: &str) -> Result<Vec<ChineseDictionaryElement>> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
@@ -130,7 +758,7 @@ fn analyze_hsk_levels(words: &[ChineseDictionaryElement], chars: &[ChineseCharac
     println!("\n🔤 WORD DICTIONARY ANALYSIS:");
     println!("{}", "-".repeat(60));
 
-    let mut word_hsk_counts = vec![0; 11]; // HSK 1-9 + level 10 (no HSK) + no statistics
+    let mut word_hsk_counts = [0; 11]; // HSK 1-9 + level 10 (no HSK) + no statistics
     let mut words_with_stats = 0;
     let mut words_without_stats = 0;
 
@@ -152,10 +780,10 @@ fn analyze_hsk_levels(words: &[ChineseDictionaryElement], chars: &[ChineseCharac
     println!();
 
     println!("HSK Level Distribution:");
-    for level in 1..=9 {
-        if word_hsk_counts[level] > 0 {
-            let percentage = (word_hsk_counts[level] as f64 / words_with_stats as f64) * 100.0;
-            println!("  HSK {}: {:>6} words ({:>5.2}%)", level, word_hsk_counts[level], percentage);
+    for (level, &count) in word_hsk_counts.iter().enumerate().take(10).skip(1) {
+        if count > 0 {
+            let percentage = (count as f64 / words_with_stats as f64) * 100.0;
+            println!("  HSK {}: {:>6} words ({:>5.2}%)", level, count, percentage);
         }
     }
 
@@ -169,7 +797,7 @@ fn analyze_hsk_levels(words: &[ChineseDictionaryElement], chars: &[ChineseCharac
     println!("\n📝 CHARACTER DICTIONARY ANALYSIS:");
     println!("{}", "-".repeat(60));
 
-    let mut char_hsk_counts = vec![0; 11]; // HSK 1-9 + level 10 (no HSK) + no statistics
+    let mut char_hsk_counts = [0; 11]; // HSK 1-9 + level 10 (no HSK) + no statistics
     let mut chars_with_stats = 0;
     let mut chars_without_stats = 0;
 
@@ -193,10 +821,10 @@ fn analyze_hsk_levels(words: &[ChineseDictionaryElement], chars: &[ChineseCharac
     println!();
 
     println!("HSK Level Distribution:");
-    for level in 1..=9 {
-        if char_hsk_counts[level] > 0 {
-            let percentage = (char_hsk_counts[level] as f64 / chars_with_stats as f64) * 100.0;
-            println!("  HSK {}: {:>6} characters ({:>5.2}%)", level, char_hsk_counts[level], percentage);
+    for (level, &count) in char_hsk_counts.iter().enumerate().take(10).skip(1) {
+        if count > 0 {
+            let percentage = (count as f64 / chars_with_stats as f64) * 100.0;
+            println!("  HSK {}: {:>6} characters ({:>5.2}%)", level, count, percentage);
         }
     }
 
@@ -210,19 +838,23 @@ fn analyze_hsk_levels(words: &[ChineseDictionaryElement], chars: &[ChineseCharac
     println!("\n✅ Analysis complete!");
 }
 
-fn extract_hsk_words(words: &[ChineseDictionaryElement]) -> HashMap<u8, Vec<String>> {
+fn extract_hsk_words(words: &[ChineseDictionaryElement], script: Script) -> HashMap<u8, Vec<String>> {
     let mut hsk_words: HashMap<u8, Vec<String>> = HashMap::new();
+    let mut seen = HashSet::new();
 
     for word in words {
         if let Some(ref stats) = word.statistics {
             let level = stats.hsk_level;
 
             // Only include HSK levels 1-9 (exclude level 10 which means "no HSK")
-            if level >= 1 && level <= 9 {
+            // Several traditional words can share one simplified form (發/髮 → 发),
+            // so each form is only listed once per level
+            let form = script.word_form(word);
+            if (1..=9).contains(&level) && seen.insert((level, form)) {
                 hsk_words
                     .entry(level as u8)
-                    .or_insert_with(Vec::new)
-                    .push(word.trad.clone());
+                    .or_default()
+                    .push(form.to_string());
             }
         }
     }
@@ -237,32 +869,317 @@ fn extract_hsk_words(words: &[ChineseDictionaryElement]) -> HashMap<u8, Vec<Stri
     hsk_words
 }
 
-fn extract_word_glosses(words: &[ChineseDictionaryElement]) -> HashMap<String, Vec<String>> {
-    let mut glosses = HashMap::new();
+fn extract_stroke_counts(chars: &[ChineseCharacter]) -> HashMap<String, u32> {
+    chars
+        .iter()
+        .filter_map(|char_entry| {
+            let strokes = u32::try_from(char_entry.stroke_count).ok().filter(|&s| s > 0)?;
+            Some((char_entry.char.clone(), strokes))
+        })
+        .collect()
+}
+
+fn extract_char_readings(chars: &[ChineseCharacter]) -> HashMap<String, Vec<Reading>> {
+    chars
+        .iter()
+        .filter_map(|char_entry| {
+            let frequencies: Vec<(String, i64)> = char_entry
+                .pinyin_frequencies
+                .iter()
+                .flatten()
+                .map(|frequency| (frequency.pinyin.clone(), frequency.count))
+                .collect();
+            let readings = readings_by_frequency(&frequencies);
+            (!readings.is_empty()).then(|| (char_entry.char.clone(), readings))
+        })
+        .collect()
+}
+
+fn extract_char_pinyin(chars: &[ChineseCharacter]) -> HashMap<String, Vec<String>> {
+    extract_char_readings(chars)
+        .into_iter()
+        .map(|(character, readings)| (character, readings.into_iter().map(|r| r.marked).collect()))
+        .collect()
+}
 
+/// Readings of every HSK word and character, most frequent first where the dictionary counts them
+fn extract_hsk_pinyin(
+    words: &[ChineseDictionaryElement],
+    chars: &[ChineseCharacter],
+    hsk_words: &HashMap<u8, Vec<String>>,
+    script: Script,
+) -> (BTreeMap<String, Vec<Reading>>, BTreeMap<String, Vec<Reading>>) {
+    let hsk_forms: HashSet<&str> = hsk_words.values().flatten().map(String::as_str).collect();
+    let char_readings = extract_char_readings(chars);
+
+    // Readings of the dictionary items that apply to this script, in dictionary order
+    let mut item_readings: HashMap<&str, Vec<&str>> = HashMap::new();
     for word in words {
-        let mut all_definitions = Vec::new();
+        let form = script.word_form(word);
+        if !hsk_forms.contains(form) {
+            continue;
+        }
+        let readings = item_readings.entry(form).or_default();
+        for item in word.items.iter().filter(|item| script.includes_item(item)) {
+            readings.extend(item.pinyin.as_deref());
+        }
+    }
 
-        // Collect all definitions from all items
-        for item in &word.items {
-            if let Some(ref definitions) = item.definitions {
-                all_definitions.extend(definitions.clone());
+    let mut word_pinyin = BTreeMap::new();
+    for (form, readings) in item_readings {
+        let mut readings = distinct_readings(readings);
+        // Single characters can be ordered by how often each reading is used
+        if let Some(counted) = char_readings.get(form) {
+            let share = |reading: &Reading| {
+                counted.iter().find(|c| c.numbered == reading.numbered).and_then(|c| c.share)
+            };
+            for reading in &mut readings {
+                reading.share = share(reading);
             }
+            readings.sort_by(|a, b| b.share.unwrap_or(0.0).total_cmp(&a.share.unwrap_or(0.0)));
         }
+        if !readings.is_empty() {
+            word_pinyin.insert(form.to_string(), readings);
+        }
+    }
+
+    let mut char_pinyin = BTreeMap::new();
+    for character in hsk_forms.iter().flat_map(|word| word.chars()).map(String::from) {
+        // Characters the dictionary has no counts for fall back to their word entry
+        let readings = char_readings
+            .get(&character)
+            .or_else(|| word_pinyin.get(&character))
+            .cloned();
+        if let Some(readings) = readings {
+            char_pinyin.insert(character, readings);
+        }
+    }
+
+    (char_pinyin, word_pinyin)
+}
+
+fn write_pinyin(
+    chinese_words: &[ChineseDictionaryElement],
+    chinese_chars: &[ChineseCharacter],
+    hsk_words: &HashMap<u8, Vec<String>>,
+    script: Script,
+    output_dir: &str,
+) -> Result<()> {
+    println!("\n📖 Extracting {} pinyin...", script.label());
+    let (char_pinyin, word_pinyin) = extract_hsk_pinyin(chinese_words, chinese_chars, hsk_words, script);
+    println!("  ✅ Extracted readings of {} characters and {} words", char_pinyin.len(), word_pinyin.len());
+
+    save_pinyin(&char_pinyin, &format!("{}/char_pinyin.json", output_dir)).context("Failed to save character pinyin")?;
+    save_pinyin(&word_pinyin, &format!("{}/word_pinyin.json", output_dir)).context("Failed to save word pinyin")?;
+
+    println!("\n🔊 Finding pinyin round targets (at most {} homophones)...", MAX_HOMOPHONES);
+    let pinyin_rounds = build_pinyin_rounds(hsk_words, &char_pinyin, MAX_HOMOPHONES);
+    for (level, round_level) in &pinyin_rounds {
+        println!(
+            "  HSK {}: {} targets, {} shared readings",
+            level,
+            round_level.targets.len(),
+            round_level.homophones.len()
+        );
+    }
+    save_pinyin_rounds(&pinyin_rounds, &format!("{}/pinyin_rounds.json", output_dir))
+        .context("Failed to save pinyin rounds")?;
+    Ok(())
+}
+
+fn save_pinyin_rounds(pinyin_rounds: &BTreeMap<u8, PinyinRoundLevel>, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(pinyin_rounds)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved pinyin rounds to {}", path);
+    Ok(())
+}
+
+fn save_pinyin(pinyin: &BTreeMap<String, Vec<Reading>>, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(pinyin)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved pinyin to {}", path);
+    Ok(())
+}
+
+/// Corpus ranks of every word form and every character in the dictionaries
+fn extract_corpus_ranks(
+    words: &[ChineseDictionaryElement],
+    chars: &[ChineseCharacter],
+    script: Script,
+) -> (HashMap<String, CorpusRanks>, HashMap<String, CorpusRanks>) {
+    let mut word_ranks: HashMap<String, CorpusRanks> = HashMap::new();
+    for word in words {
+        if let Some(ref stats) = word.statistics {
+            // Words sharing a form count as common as the most common of them
+            word_ranks.entry(script.word_form(word).to_string()).or_default().merge(CorpusRanks {
+                movie: stats.movie_word_rank,
+                book: stats.book_word_rank,
+            });
+        }
+    }
+
+    let char_ranks = chars
+        .iter()
+        .filter_map(|char_entry| {
+            let stats = char_entry.statistics.as_ref()?;
+            let ranks = CorpusRanks {
+                movie: stats.movie_char_rank,
+                book: stats.book_char_rank,
+            };
+            Some((char_entry.char.clone(), ranks))
+        })
+        .collect();
+
+    (word_ranks, char_ranks)
+}
+
+fn write_frequency(
+    chinese_words: &[ChineseDictionaryElement],
+    chinese_chars: &[ChineseCharacter],
+    hsk_words: &HashMap<u8, Vec<String>>,
+    script: Script,
+    output_dir: &str,
+) -> Result<()> {
+    println!("\n📊 Ranking {} words and characters by frequency...", script.label());
+    let (word_ranks, char_ranks) = extract_corpus_ranks(chinese_words, chinese_chars, script);
+
+    let word_levels: BTreeMap<u8, Vec<String>> = hsk_words.iter().map(|(&level, words)| (level, words.clone())).collect();
+    let mut char_levels: BTreeMap<u8, Vec<String>> = BTreeMap::new();
+    for (character, level) in character_levels(hsk_words) {
+        char_levels.entry(level).or_default().push(character);
+    }
+
+    let word_frequency = build_frequency_levels(&word_levels, &word_ranks);
+    let char_frequency = build_frequency_levels(&char_levels, &char_ranks);
+    let ranked = |levels: &BTreeMap<u8, Vec<FrequencyEntry>>| {
+        levels.values().flatten().filter(|e| e.movie_rank.is_some() || e.book_rank.is_some()).count()
+    };
+    println!(
+        "  ✅ {} words and {} characters have corpus ranks",
+        ranked(&word_frequency),
+        ranked(&char_frequency)
+    );
+
+    save_frequency(&word_frequency, &format!("{}/word_frequency.json", output_dir))
+        .context("Failed to save word frequency")?;
+    save_frequency(&char_frequency, &format!("{}/char_frequency.json", output_dir))
+        .context("Failed to save character frequency")?;
+    Ok(())
+}
+
+fn save_frequency(frequency: &BTreeMap<u8, Vec<FrequencyEntry>>, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(frequency)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved frequency to {}", path);
+    Ok(())
+}
+
+fn extract_component_annotations(chars: &[ChineseCharacter]) -> HashMap<String, Vec<ComponentAnnotation>> {
+    chars
+        .iter()
+        .filter_map(|char_entry| {
+            let components = char_entry.components.as_ref().filter(|c| !c.is_empty())?;
+            let annotations = components
+                .iter()
+                .map(|component| ComponentAnnotation {
+                    component: component.character.clone(),
+                    roles: component.component_type.iter().map(|t| ComponentRole::parse(t)).collect(),
+                    hint: component.hint.clone().filter(|hint| !hint.trim().is_empty()),
+                })
+                .collect();
+            Some((char_entry.char.clone(), annotations))
+        })
+        .collect()
+}
+
+/// Share of each character's uses read each way, for ranking the definitions of single characters
+fn extract_reading_shares(chars: &[ChineseCharacter]) -> HashMap<String, ReadingShares> {
+    chars
+        .iter()
+        .filter_map(|char_entry| {
+            let frequencies: Vec<(String, i64)> = char_entry
+                .pinyin_frequencies
+                .iter()
+                .flatten()
+                .map(|frequency| (frequency.pinyin.clone(), frequency.count))
+                .collect();
+            let shares = reading_shares(&frequencies);
+            (!shares.is_empty()).then(|| (char_entry.char.clone(), shares))
+        })
+        .collect()
+}
+
+fn extract_word_glosses(
+    words: &[ChineseDictionaryElement],
+    script: Script,
+    reading_shares: &HashMap<String, ReadingShares>,
+    report: &mut RedactionReport,
+) -> HashMap<String, Vec<String>> {
+    // Words sharing a form are merged before ranking
+    let mut candidates: HashMap<String, (Vec<GlossCandidate>, Spoilers)> = HashMap::new();
+
+    for word in words {
+        let (definitions, spoilers) = candidates.entry(script.word_form(word).to_string()).or_default();
+        spoilers.forms.extend([word.simp.clone(), word.trad.clone()]);
+
+        // Collect all definitions from the items that apply to this script
+        for item in word.items.iter().filter(|item| script.includes_item(item)) {
+            for text in item.definitions.iter().flatten() {
+                definitions.push(GlossCandidate {
+                    text: text.clone(),
+                    source: item.source.clone(),
+                    reading: item.pinyin.clone(),
+                });
+            }
+            spoilers.readings.extend(item.pinyin.clone());
+        }
+    }
+
+    let mut glosses: HashMap<String, Vec<String>> = HashMap::new();
+    for (form, (definitions, spoilers)) in candidates {
+        // Best clue first; a definition must not spell out the word it defines
+        let ranked = rank_glosses(definitions, reading_shares.get(&form));
+        let ranked = report.sanitize(&form, ranked, &spoilers);
 
         // Only add if we found at least one definition
-        if !all_definitions.is_empty() {
-            glosses.insert(word.trad.clone(), all_definitions);
+        if !ranked.is_empty() {
+            glosses.insert(form, ranked);
         }
     }
 
+    report.finish();
     glosses
 }
 
-fn extract_char_glosses_with_top_words(chars: &[ChineseCharacter]) -> HashMap<String, Vec<String>> {
+/// The forms and readings that give `char_entry` away in a clue
+fn char_spoilers(char_entry: &ChineseCharacter) -> Spoilers {
+    let forms = std::iter::once(&char_entry.char)
+        .chain(&char_entry.variant_of)
+        .chain(char_entry.simp_variants.iter().flatten())
+        .chain(char_entry.trad_variants.iter().flatten())
+        .cloned()
+        .collect();
+    let readings = char_entry
+        .pinyin_frequencies
+        .iter()
+        .flatten()
+        .map(|frequency| frequency.pinyin.clone())
+        .collect();
+    Spoilers { forms, readings }
+}
+
+fn extract_char_glosses_with_top_words(
+    chars: &[ChineseCharacter],
+    script: Script,
+    report: &mut RedactionReport,
+) -> HashMap<String, Vec<String>> {
     let mut glosses = HashMap::new();
 
     for char_entry in chars {
+        let spoilers = char_spoilers(char_entry);
         let mut all_definitions = Vec::new();
 
         // Add the main gloss if available
@@ -273,55 +1190,82 @@ fn extract_char_glosses_with_top_words(chars: &[ChineseCharacter]) -> HashMap<St
         // Add top 3 words with underscores showing where the character appears
         if let Some(ref stats) = char_entry.statistics {
             if let Some(ref top_words) = stats.top_words {
-                for top_word in top_words.iter().take(3) {
-                    // Try to replace the character with underscore in the word
-                    // Check both simplified (word) and traditional (trad) forms
-                    let mut word_with_underscore = top_word.word.replace(&char_entry.char, "_");
-
-                    // If no replacement in simplified, try traditional form
-                    if !word_with_underscore.contains('_') && top_word.trad != top_word.word {
-                        let trad_with_underscore = top_word.trad.replace(&char_entry.char, "_");
-                        if trad_with_underscore.contains('_') {
-                            word_with_underscore = trad_with_underscore;
-                        }
-                    }
-
-                    // If still no underscore, try replacing the variantOf character
-                    // (e.g., 龢 is a variant of 和, so replace 和 with _)
-                    if !word_with_underscore.contains('_') {
-                        if let Some(ref variant_of) = char_entry.variant_of {
-                            let variant_replaced = top_word.word.replace(variant_of, "_");
-                            if variant_replaced.contains('_') {
-                                word_with_underscore = variant_replaced;
-                            } else if top_word.trad != top_word.word {
-                                let trad_variant_replaced = top_word.trad.replace(variant_of, "_");
-                                if trad_variant_replaced.contains('_') {
-                                    word_with_underscore = trad_variant_replaced;
-                                }
-                            }
-                        }
-                    }
-
-                    // If still no underscore after all attempts, just use _ as fallback
-                    if !word_with_underscore.contains('_') {
-                        word_with_underscore = String::from("_");
+                let mut added = 0;
+                for top_word in top_words {
+                    if added == 3 {
+                        break;
                     }
+                    // Show the chosen script's form of the word if it contains the character
+                    // (or one of its variants, e.g. 和 for 龢), otherwise the other form
+                    let (word, other) = match script {
+                        Script::Simp => (&top_word.word, &top_word.trad),
+                        Script::Trad => (&top_word.trad, &top_word.word),
+                    };
+                    // A word without the character would be a clue to a different answer
+                    let Some(word) = [word, other].into_iter().find(|w| spoilers.appears_in(w)) else {
+                        continue;
+                    };
 
-                    let formatted = format!("{} ({})", word_with_underscore, top_word.gloss);
+                    let formatted = format!("{} ({})", spoilers.redact_forms(word), top_word.gloss);
                     all_definitions.push(formatted);
+                    added += 1;
                 }
             }
         }
 
+        let all_definitions = report.sanitize(&char_entry.char, all_definitions, &spoilers);
+
         // Only add if we found at least one definition
         if !all_definitions.is_empty() {
             glosses.insert(char_entry.char.clone(), all_definitions);
         }
     }
 
+    report.finish();
     glosses
 }
 
+fn save_word_clues(clues: &BTreeMap<&String, RankedClues>, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(clues)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved primary and backup clues to {}", path);
+    Ok(())
+}
+
+/// Redaction reports for word_glosses.json and char_glosses.json
+#[derive(Debug, Default, Serialize)]
+struct GlossRedactions {
+    words: RedactionReport,
+    characters: RedactionReport,
+}
+
+fn print_redactions(report: &RedactionReport) {
+    println!(
+        "  🙈 Redacted {} of {} clues that gave the answer away, dropped {}",
+        report.redacted, report.clues, report.dropped
+    );
+    if !report.without_clues.is_empty() {
+        println!("  ⚠️  {} entries have no clue left", report.without_clues.len());
+    }
+}
+
+fn save_gloss_redactions(redactions: &GlossRedactions, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(redactions)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved gloss redaction report to {}", path);
+    Ok(())
+}
+
+fn save_script_variants(variants: &ScriptVariants, path: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    let json = serde_json::to_string_pretty(variants)?;
+    file.write_all(json.as_bytes())?;
+    println!("  ✅ Saved script variants to {}", path);
+    Ok(())
+}
+
 fn save_word_glosses(glosses: &HashMap<String, Vec<String>>, path: &str) -> Result<()> {
     let mut file = File::create(path)?;
     let json = serde_json::to_string_pretty(glosses)?;
@@ -0,0 +1,193 @@
+// phonetic_series.rs - Families of characters sharing a sound component
+//
+// 清, 請, 情 and 晴 all borrow their sound from 青. Grouping HSK characters by the
+// phonetic component the dictionary labels (see component_roles.rs) gives these families,
+// each with the readings of its members. Radical variants are folded so a phonetic written
+// two ways is one series.
+//
+// A series is only as useful as it is regular: the regularity score is the share of
+// members whose main reading has the series' most common syllable, ignoring tone
+// (青 series: qīng, qíng, qǐng, qíng → 1.0). The tone-exact share is reported as well.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::component_roles::ComponentRoles;
+use crate::curriculum::character_levels;
+use crate::pinyin::strip_tone;
+use crate::radicals::fold_component;
+
+/// A character in a phonetic series
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeriesMember {
+    pub character: String,
+    /// Lowest HSK level of any word containing the character
+    pub level: u8,
+    /// Readings, most frequent first
+    pub pinyin: Vec<String>,
+}
+
+/// All HSK characters using one phonetic component, saved in phonetic_series.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhoneticSeries {
+    pub phonetic: String,
+    /// Readings of the phonetic component itself, most frequent first (empty if unknown)
+    pub phonetic_pinyin: Vec<String>,
+    /// Members ordered by HSK level, then code point
+    pub members: Vec<SeriesMember>,
+    /// Most common toneless syllable among the members' main readings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub syllable: Option<String>,
+    /// Share of members with a known reading whose main reading has `syllable`, ignoring tone
+    pub regularity: f64,
+    /// Share of members with a known reading whose main reading is the most common one, tone included
+    pub tone_regularity: f64,
+}
+
+/// Share of `readings` equal to the most common one, and that reading
+/// Ties prefer the phonetic's own reading, then the alphabetically first
+fn most_common_share(readings: &[String], preferred: Option<&str>) -> (Option<String>, f64) {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for reading in readings {
+        *counts.entry(reading.as_str()).or_default() += 1;
+    }
+    let best = counts
+        .iter()
+        .max_by_key(|&(&reading, &count)| (count, Some(reading) == preferred, std::cmp::Reverse(reading)));
+    match best {
+        Some((reading, &count)) => (Some(reading.to_string()), count as f64 / readings.len() as f64),
+        None => (None, 0.0),
+    }
+}
+
+/// Group HSK characters by their phonetic components; series need at least `min_members`
+pub fn build_phonetic_series(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    component_roles: &BTreeMap<String, ComponentRoles>,
+    char_pinyin: &HashMap<String, Vec<String>>,
+    min_members: usize,
+) -> BTreeMap<String, PhoneticSeries> {
+    let levels = character_levels(hsk_words);
+
+    let mut members_by_phonetic: BTreeMap<String, Vec<SeriesMember>> = BTreeMap::new();
+    for (character, roles) in component_roles {
+        let Some(&level) = levels.get(character) else {
+            continue;
+        };
+        for phonetic in &roles.phonetic {
+            members_by_phonetic
+                .entry(fold_component(phonetic).into_owned())
+                .or_default()
+                .push(SeriesMember {
+                    character: character.clone(),
+                    level,
+                    pinyin: char_pinyin.get(character).cloned().unwrap_or_default(),
+                });
+        }
+    }
+
+    members_by_phonetic
+        .into_iter()
+        .filter(|(_, members)| members.len() >= min_members)
+        .map(|(phonetic, mut members)| {
+            members.sort_by(|a, b| (a.level, &a.character).cmp(&(b.level, &b.character)));
+            let phonetic_pinyin = char_pinyin.get(&phonetic).cloned().unwrap_or_default();
+
+            let main_readings: Vec<String> = members
+                .iter()
+                .filter_map(|member| member.pinyin.first())
+                .map(|reading| reading.trim().to_lowercase())
+                .collect();
+            let syllables: Vec<String> = main_readings.iter().map(|r| strip_tone(r)).collect();
+            let own_reading = phonetic_pinyin.first().map(|r| r.trim().to_lowercase());
+            let own_syllable = own_reading.as_deref().map(strip_tone);
+            let (syllable, regularity) = most_common_share(&syllables, own_syllable.as_deref());
+            let (_, tone_regularity) = most_common_share(&main_readings, own_reading.as_deref());
+
+            let series = PhoneticSeries {
+                phonetic: phonetic.clone(),
+                phonetic_pinyin,
+                members,
+                syllable,
+                regularity,
+                tone_regularity,
+            };
+            (phonetic, series)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roles(phonetic: &[&str]) -> ComponentRoles {
+        ComponentRoles {
+            semantic: Vec::new(),
+            phonetic: phonetic.iter().map(|p| p.to_string()).collect(),
+            components: Vec::new(),
+        }
+    }
+
+    fn pinyin(entries: &[(&str, &str)]) -> HashMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(c, p)| (c.to_string(), p.split(',').map(str::to_string).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_series_groups_characters_by_phonetic() {
+        let hsk_words = HashMap::from([
+            (1, vec!["請".to_string(), "晴天".to_string()]),
+            (2, vec!["清".to_string(), "情".to_string(), "猜".to_string()]),
+        ]);
+        let component_roles = BTreeMap::from([
+            ("請".to_string(), roles(&["青"])),
+            ("晴".to_string(), roles(&["青"])),
+            ("清".to_string(), roles(&["青"])),
+            ("情".to_string(), roles(&["青"])),
+            ("猜".to_string(), roles(&["青"])),
+            ("河".to_string(), roles(&["可"])),
+        ]);
+        let char_pinyin = pinyin(&[
+            ("青", "qīng"),
+            ("請", "qǐng"),
+            ("晴", "qíng"),
+            ("清", "qīng"),
+            ("情", "qíng"),
+            ("猜", "cāi"),
+        ]);
+        let series = build_phonetic_series(&hsk_words, &component_roles, &char_pinyin, 2);
+
+        // 河 is not an HSK character here, so 可 has no series
+        assert_eq!(series.keys().collect::<Vec<_>>(), vec!["青"]);
+        let qing = &series["青"];
+        assert_eq!(qing.phonetic_pinyin, vec!["qīng"]);
+        let members: Vec<&str> = qing.members.iter().map(|m| m.character.as_str()).collect();
+        assert_eq!(members, vec!["晴", "請", "情", "清", "猜"]);
+        assert_eq!(qing.syllable.as_deref(), Some("qing"));
+        assert!((qing.regularity - 0.8).abs() < 1e-9);
+        assert!((qing.tone_regularity - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_members_without_readings_do_not_count() {
+        let hsk_words = HashMap::from([(1, vec!["河".to_string(), "何".to_string(), "苛".to_string()])]);
+        let component_roles = BTreeMap::from([
+            ("河".to_string(), roles(&["可"])),
+            ("何".to_string(), roles(&["可"])),
+            ("苛".to_string(), roles(&["可"])),
+        ]);
+        let char_pinyin = pinyin(&[("河", "hé"), ("何", "hé,hè")]);
+        let series = build_phonetic_series(&hsk_words, &component_roles, &char_pinyin, 2);
+
+        let ke = &series["可"];
+        assert_eq!(ke.members.len(), 3);
+        assert_eq!(ke.members[0].pinyin, vec!["hé", "hè"]);
+        assert_eq!(ke.syllable.as_deref(), Some("he"));
+        assert_eq!(ke.regularity, 1.0);
+        assert_eq!(ke.tone_regularity, 1.0);
+        assert!(build_phonetic_series(&hsk_words, &component_roles, &char_pinyin, 4).is_empty());
+    }
+}
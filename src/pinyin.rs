@@ -0,0 +1,259 @@
+// pinyin.rs - Pinyin readings: tone marks, tone numbers and normalization
+//
+// The dictionaries write pinyin with tone marks (qīng); some sources use tone numbers
+// (qing1), CC-CEDICT writes ü as u: (lu:4) and keyboards type v. Comparing readings
+// regardless of tone needs the bare syllable, finding a reading in CC-CEDICT style text
+// ([qing1]) needs the numbered form, and showing one needs tone marks. Any spelling is
+// normalized to lowercase numbered syllables separated by single spaces ("ming2 bai5"),
+// with 5 for the neutral tone.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Vowels with a tone mark and the plain vowel they carry it on
+const TONE_MARKS: [(char, char); 24] = [
+    ('ā', 'a'), ('á', 'a'), ('ǎ', 'a'), ('à', 'a'),
+    ('ē', 'e'), ('é', 'e'), ('ě', 'e'), ('è', 'e'),
+    ('ī', 'i'), ('í', 'i'), ('ǐ', 'i'), ('ì', 'i'),
+    ('ō', 'o'), ('ó', 'o'), ('ǒ', 'o'), ('ò', 'o'),
+    ('ū', 'u'), ('ú', 'u'), ('ǔ', 'u'), ('ù', 'u'),
+    ('ǖ', 'ü'), ('ǘ', 'ü'), ('ǚ', 'ü'), ('ǜ', 'ü'),
+];
+
+/// Apostrophes that separate syllables in a word ("Xī'ān")
+const APOSTROPHES: [char; 2] = ['\'', '’'];
+
+/// The syllable without its tone, lowercased: "Qīng" and "qing1" both become "qing"
+/// Only pinyin should be passed in: a v is read as ü where it can be one (lv4, nve4)
+pub fn strip_tone(syllable: &str) -> String {
+    let bare: Vec<char> = syllable
+        .trim()
+        .replace("u:", "ü")
+        .replace("U:", "Ü")
+        .chars()
+        .filter(|c| !c.is_ascii_digit())
+        .flat_map(char::to_lowercase)
+        .map(|c| {
+            TONE_MARKS
+                .iter()
+                .find(|&&(marked, _)| marked == c)
+                .map_or(c, |&(_, plain)| plain)
+        })
+        .collect();
+    (0..bare.len())
+        .map(|index| if is_typed_u_umlaut(&bare, index) { 'ü' } else { bare[index] })
+        .collect()
+}
+
+/// Whether the character at `index` is a v typed for ü: a syllable opening with l or n
+/// followed by v or ve ("lv", "nve"), so "very" and "solve" keep their v
+fn is_typed_u_umlaut(bare: &[char], index: usize) -> bool {
+    let ends_syllable = |at: usize| bare.get(at).is_none_or(|c| !c.is_alphabetic());
+    bare[index] == 'v'
+        && index > 0
+        && matches!(bare[index - 1], 'l' | 'n')
+        && (index == 1 || !bare[index - 2].is_alphabetic())
+        && (ends_syllable(index + 1) || (bare[index + 1] == 'e' && ends_syllable(index + 2)))
+}
+
+/// The tone of a syllable, 1–4 from a tone mark or trailing digit, 5 for the neutral tone
+/// A trailing 0 or 5 marks the neutral tone; any other digit is not a tone
+pub fn tone_of(syllable: &str) -> u8 {
+    match syllable.trim().chars().last().and_then(|c| c.to_digit(10)) {
+        Some(digit @ 1..=4) => return digit as u8,
+        Some(0 | 5) => return 5,
+        _ => {}
+    }
+    syllable
+        .chars()
+        .flat_map(char::to_lowercase)
+        .find_map(|c| TONE_MARKS.iter().position(|&(marked, _)| marked == c))
+        .map_or(5, |index| (index % 4) as u8 + 1)
+}
+
+/// A reading with tone numbers instead of tone marks: "míng bai" becomes "ming2 bai5"
+/// Already numbered readings are normalized the same way ("Lu:4" becomes "lü4"), and
+/// syllables joined by an apostrophe are separated ("Xī'ān" becomes "xi1 an1")
+pub fn to_numbered(reading: &str) -> String {
+    reading
+        .split(|c: char| c.is_whitespace() || APOSTROPHES.contains(&c))
+        .filter(|syllable| !syllable.is_empty())
+        .map(|syllable| {
+            if syllable.chars().any(char::is_alphabetic) {
+                format!("{}{}", strip_tone(syllable), tone_of(syllable))
+            } else {
+                syllable.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A reading with tone marks instead of tone numbers: "ming2 bai5" becomes "míng bai"
+/// The mark goes on a or e if present, on the o of ou, and otherwise on the last vowel;
+/// apostrophes between syllables are kept ("xi1'an1" becomes "xī'ān")
+pub fn to_marked(reading: &str) -> String {
+    reading
+        .split_whitespace()
+        .map(|word| word.split(APOSTROPHES).map(mark_syllable).collect::<Vec<_>>().join("'"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// One numbered syllable with its tone mark
+fn mark_syllable(syllable: &str) -> String {
+    let tone = tone_of(syllable);
+    let bare = strip_tone(syllable);
+    if tone == 5 {
+        return bare;
+    }
+    let position = bare
+        .find('a')
+        .or_else(|| bare.find('e'))
+        .or_else(|| bare.find("ou"))
+        .or_else(|| bare.rfind(|c| "iouü".contains(c)));
+    let Some(position) = position else {
+        return bare;
+    };
+    let vowel = bare[position..].chars().next().expect("position is a char boundary");
+    let marked = TONE_MARKS
+        .iter()
+        .filter(|&&(_, plain)| plain == vowel)
+        .nth(tone as usize - 1)
+        .map_or(vowel, |&(marked, _)| marked);
+    format!("{}{}{}", &bare[..position], marked, &bare[position + vowel.len_utf8()..])
+}
+
+/// The canonical spelling of any reading, for comparing readings written different ways
+pub fn normalize(reading: &str) -> String {
+    to_numbered(reading)
+}
+
+/// One reading of a character or word, saved in char_pinyin.json and word_pinyin.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    /// With tone marks, for display ("míng bai")
+    pub marked: String,
+    /// With tone numbers, for matching typed input ("ming2 bai5")
+    pub numbered: String,
+    /// Share of uses with this reading, where the dictionary counts them
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub share: Option<f64>,
+}
+
+impl Reading {
+    /// A reading from either spelling
+    pub fn new(reading: &str, share: Option<f64>) -> Reading {
+        Reading {
+            marked: to_marked(reading),
+            numbered: to_numbered(reading),
+            share,
+        }
+    }
+}
+
+/// Readings from (reading, count) pairs, most frequent first; equal counts keep their order
+/// Spellings of the same reading are merged
+pub fn readings_by_frequency(frequencies: &[(String, i64)]) -> Vec<Reading> {
+    let total: i64 = frequencies.iter().map(|(_, count)| (*count).max(0)).sum();
+    let mut counts: Vec<(String, i64)> = Vec::new();
+    for (reading, count) in frequencies {
+        let numbered = normalize(reading);
+        match counts.iter_mut().find(|(seen, _)| *seen == numbered) {
+            Some((_, total)) => *total += (*count).max(0),
+            None => counts.push((numbered, (*count).max(0))),
+        }
+    }
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    counts
+        .into_iter()
+        .map(|(reading, count)| {
+            let share = (total > 0).then(|| count as f64 / total as f64);
+            Reading::new(&reading, share)
+        })
+        .collect()
+}
+
+/// Distinct readings in the order given
+pub fn distinct_readings<'a>(readings: impl IntoIterator<Item = &'a str>) -> Vec<Reading> {
+    let mut seen = HashSet::new();
+    readings
+        .into_iter()
+        .filter(|reading| !reading.trim().is_empty() && seen.insert(normalize(reading)))
+        .map(|reading| Reading::new(reading, None))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_marks_and_numbers_are_stripped() {
+        assert_eq!(strip_tone("qīng"), "qing");
+        assert_eq!(strip_tone("qing1"), "qing");
+        assert_eq!(strip_tone("Lǜ"), "lü");
+        assert_eq!(strip_tone("ma"), "ma");
+        assert_eq!(strip_tone("Ōu"), "ou");
+        assert_eq!(strip_tone("LÜE4"), "lüe");
+    }
+
+    #[test]
+    fn test_only_a_pinyin_v_becomes_u_umlaut() {
+        assert_eq!(strip_tone("lv4"), "lü");
+        assert_eq!(strip_tone("nve4"), "nüe");
+        assert_eq!(strip_tone("very"), "very");
+        assert_eq!(strip_tone("solve"), "solve");
+        assert_eq!(strip_tone("nvidia"), "nvidia");
+    }
+
+    #[test]
+    fn test_tone_marks_become_numbers() {
+        assert_eq!(tone_of("fá"), 2);
+        assert_eq!(tone_of("lǜ"), 4);
+        assert_eq!(tone_of("qing3"), 3);
+        assert_eq!(tone_of("ma"), 5);
+        assert_eq!(tone_of("qing0"), 5);
+        assert_eq!(tone_of("ma5"), 5);
+        assert_eq!(tone_of("qīng7"), 1);
+        assert_eq!(to_numbered("míng bai"), "ming2 bai5");
+        assert_eq!(to_numbered("Lu:4"), "lü4");
+        // Capitalized tone marks and apostrophes in place names
+        assert_eq!(to_numbered("Ōu zhōu"), "ou1 zhou1");
+        assert_eq!(to_numbered("Ān huī"), "an1 hui1");
+        assert_eq!(to_numbered("Xī'ān"), "xi1 an1");
+        assert_eq!(to_numbered("Xī’ān"), "xi1 an1");
+    }
+
+    #[test]
+    fn test_numbers_become_tone_marks() {
+        assert_eq!(to_marked("ming2 bai5"), "míng bai");
+        assert_eq!(to_marked("hao3"), "hǎo");
+        assert_eq!(to_marked("xie4"), "xiè");
+        assert_eq!(to_marked("gou3"), "gǒu");
+        assert_eq!(to_marked("gui4"), "guì");
+        assert_eq!(to_marked("liu2"), "liú");
+        assert_eq!(to_marked("lv4"), "lǜ");
+        assert_eq!(to_marked("er2"), "ér");
+        assert_eq!(to_marked("qing0"), "qing");
+        assert_eq!(to_marked("Xi1'an1"), "xī'ān");
+        // Round trip
+        assert_eq!(to_marked(&to_numbered("zhōng guó")), "zhōng guó");
+    }
+
+    #[test]
+    fn test_readings_are_ordered_by_frequency_and_merged() {
+        let frequencies = [
+            ("háng".to_string(), 10),
+            ("xíng".to_string(), 85),
+            ("xing2".to_string(), 5),
+        ];
+        let readings = readings_by_frequency(&frequencies);
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0], Reading { marked: "xíng".to_string(), numbered: "xing2".to_string(), share: Some(0.9) });
+        assert_eq!(readings[1].numbered, "hang2");
+
+        let readings = distinct_readings(["míng bai", "ming2 bai5", "", "míng bái"]);
+        assert_eq!(readings.iter().map(|r| r.numbered.as_str()).collect::<Vec<_>>(), vec!["ming2 bai5", "ming2 bai2"]);
+    }
+}
@@ -0,0 +1,173 @@
+// pinyin_rounds.rs - Targets for rounds whose clue is a reading instead of a gloss
+//
+// Shown "qíng", a player could be after 晴, 情 or 擎; the clue only works if few characters
+// the player is learning share the reading. Each HSK character is clued by its most
+// frequent reading and compared against the other characters introduced at the same
+// level. A character is a homophone of the clue if any of its readings matches: tone
+// included for a pinyin + tone clue, ignoring tone for a bare pinyin clue.
+//
+// Characters with at most `max_homophones` tone-exact homophones are offered as targets,
+// least ambiguous first, and the homophone sets of the level are exported alongside so the
+// client can judge ambiguity itself (or accept any homophone as an answer).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::curriculum::character_levels;
+use crate::pinyin::{strip_tone, Reading};
+
+/// Most tone-exact homophones a target may have in its level
+pub const MAX_HOMOPHONES: usize = 2;
+
+/// A character that can be the answer of a pinyin round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinyinTarget {
+    pub character: String,
+    /// The clue: the character's most frequent reading
+    pub reading: Reading,
+    /// Other same-level characters with the clue's reading, tone included
+    pub homophones: Vec<String>,
+    /// Other same-level characters with the clue's syllable in any tone
+    pub toneless_homophones: Vec<String>,
+}
+
+/// Pinyin round data of one HSK level, saved in pinyin_rounds.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PinyinRoundLevel {
+    /// Targets, fewest homophones first, then fewest toneless homophones, then code point
+    pub targets: Vec<PinyinTarget>,
+    /// Numbered reading → the level's characters with that reading, where more than one
+    pub homophones: BTreeMap<String, Vec<String>>,
+    /// Toneless syllable → the level's characters with that syllable, where more than one
+    pub toneless_homophones: BTreeMap<String, Vec<String>>,
+}
+
+/// Group the characters of each level by reading and pick the least ambiguous as targets
+pub fn build_pinyin_rounds(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    char_pinyin: &BTreeMap<String, Vec<Reading>>,
+    max_homophones: usize,
+) -> BTreeMap<u8, PinyinRoundLevel> {
+    let mut characters_by_level: BTreeMap<u8, BTreeSet<String>> = BTreeMap::new();
+    for (character, level) in character_levels(hsk_words) {
+        if char_pinyin.get(&character).is_some_and(|readings| !readings.is_empty()) {
+            characters_by_level.entry(level).or_default().insert(character);
+        }
+    }
+
+    characters_by_level
+        .into_iter()
+        .map(|(level, characters)| {
+            // Every reading of every character counts, so a rare reading can still confuse
+            let mut by_reading: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            let mut by_syllable: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+            for character in &characters {
+                for reading in &char_pinyin[character] {
+                    by_reading.entry(reading.numbered.clone()).or_default().insert(character.clone());
+                    by_syllable.entry(strip_tone(&reading.numbered)).or_default().insert(character.clone());
+                }
+            }
+
+            let others = |sets: &BTreeMap<String, BTreeSet<String>>, key: &str, character: &str| -> Vec<String> {
+                sets.get(key)
+                    .into_iter()
+                    .flatten()
+                    .filter(|other| *other != character)
+                    .cloned()
+                    .collect()
+            };
+
+            let mut targets: Vec<PinyinTarget> = characters
+                .iter()
+                .filter_map(|character| {
+                    let reading = char_pinyin[character].first()?.clone();
+                    let homophones = others(&by_reading, &reading.numbered, character);
+                    let toneless_homophones = others(&by_syllable, &strip_tone(&reading.numbered), character);
+                    (homophones.len() <= max_homophones).then(|| PinyinTarget {
+                        character: character.clone(),
+                        reading,
+                        homophones,
+                        toneless_homophones,
+                    })
+                })
+                .collect();
+            targets.sort_by(|a, b| {
+                (a.homophones.len(), a.toneless_homophones.len(), &a.character)
+                    .cmp(&(b.homophones.len(), b.toneless_homophones.len(), &b.character))
+            });
+
+            let shared = |sets: BTreeMap<String, BTreeSet<String>>| -> BTreeMap<String, Vec<String>> {
+                sets.into_iter()
+                    .filter(|(_, members)| members.len() > 1)
+                    .map(|(key, members)| (key, members.into_iter().collect()))
+                    .collect()
+            };
+
+            let round_level = PinyinRoundLevel {
+                targets,
+                homophones: shared(by_reading),
+                toneless_homophones: shared(by_syllable),
+            };
+            (level, round_level)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pinyin(entries: &[(&str, &str)]) -> BTreeMap<String, Vec<Reading>> {
+        entries
+            .iter()
+            .map(|(c, p)| (c.to_string(), p.split(',').map(|r| Reading::new(r, None)).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_homophones_are_counted_within_a_level() {
+        let hsk_words = HashMap::from([
+            (1, vec!["晴天".to_string(), "情".to_string(), "请".to_string()]),
+            (2, vec!["擎".to_string(), "行".to_string()]),
+        ]);
+        let char_pinyin = pinyin(&[
+            ("晴", "qíng"),
+            ("天", "tiān"),
+            ("情", "qíng"),
+            ("请", "qǐng"),
+            ("擎", "qíng"),
+            ("行", "xíng,háng"),
+        ]);
+        let rounds = build_pinyin_rounds(&hsk_words, &char_pinyin, 1);
+
+        // 擎 is a level 2 character, so it does not make 晴 and 情 more ambiguous
+        let level_1 = &rounds[&1];
+        assert_eq!(level_1.homophones["qing2"], vec!["情", "晴"]);
+        assert_eq!(level_1.toneless_homophones["qing"], vec!["情", "晴", "请"]);
+        assert!(!level_1.homophones.contains_key("tian1"));
+
+        let targets: Vec<&str> = level_1.targets.iter().map(|t| t.character.as_str()).collect();
+        assert_eq!(targets, vec!["天", "请", "情", "晴"]);
+        assert_eq!(level_1.targets[1].reading.marked, "qǐng");
+        assert!(level_1.targets[1].homophones.is_empty());
+        assert_eq!(level_1.targets[1].toneless_homophones, vec!["情", "晴"]);
+
+        let level_2 = &rounds[&2];
+        assert_eq!(level_2.targets[0].reading.numbered, "qing2");
+        assert!(level_2.homophones.is_empty());
+    }
+
+    #[test]
+    fn test_ambiguous_characters_are_not_targets() {
+        let hsk_words = HashMap::from([(1, vec!["是".to_string(), "事".to_string(), "市".to_string(), "水".to_string()])]);
+        let char_pinyin = pinyin(&[("是", "shì"), ("事", "shì"), ("市", "shì")]);
+
+        let rounds = build_pinyin_rounds(&hsk_words, &char_pinyin, 1);
+        // 水 has no known reading and three characters read shì
+        assert!(rounds[&1].targets.is_empty());
+        assert_eq!(rounds[&1].homophones["shi4"].len(), 3);
+
+        let rounds = build_pinyin_rounds(&hsk_words, &char_pinyin, 2);
+        assert_eq!(rounds[&1].targets.len(), 3);
+    }
+}
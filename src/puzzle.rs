@@ -0,0 +1,287 @@
+// puzzle.rs - Deterministic round generation
+//
+// A round is a target character plus a shuffled pool of the leaf components of the target
+// and a few decoy characters from the same HSK level. Everything random is drawn from a
+// ChaCha8 generator seeded by the caller, with one stream per HSK level, so the same seed
+// always produces the same puzzles_<level>.json regardless of which levels are generated.
+// Only characters the solver proved buildable are used as targets, and every round carries
+// its shortest build sequence for QA.
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+
+use crate::game_data_builder::{leaves_of, CharacterDecomposition};
+use crate::solver::{playable_characters, solve, BuildStep, Solution, SolvabilityReport};
+
+/// How rounds are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PuzzleConfig {
+    pub seed: u64,
+    /// Number of decoy characters whose components are mixed into each round
+    pub decoys: usize,
+    /// Number of rounds generated per HSK level (fewer if the level has fewer targets)
+    pub rounds_per_level: usize,
+}
+
+impl Default for PuzzleConfig {
+    /// Two decoys, as in the web app
+    fn default() -> Self {
+        PuzzleConfig {
+            seed: 0,
+            decoys: 2,
+            rounds_per_level: 100,
+        }
+    }
+}
+
+/// One round of the game
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Puzzle {
+    /// Stable identifier, "<level>-<index>"
+    pub id: String,
+    pub target: String,
+    pub decoys: Vec<String>,
+    /// Leaf components of the target and decoys, shuffled; duplicates are kept
+    pub components: Vec<String>,
+    /// Shortest build sequence from `components` (absent if the search gave up)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub solution: Option<Vec<BuildStep>>,
+}
+
+/// All rounds of one HSK level, saved as puzzles_<level>.json
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PuzzleSet {
+    pub level: u8,
+    pub seed: u64,
+    pub decoy_count: usize,
+    pub puzzles: Vec<Puzzle>,
+}
+
+/// Build one round for `target`, drawing decoys uniformly from `candidates`
+pub fn generate_puzzle(
+    rng: &mut ChaCha8Rng,
+    id: String,
+    target: &str,
+    candidates: &[String],
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    decoy_count: usize,
+) -> Puzzle {
+    let others: Vec<&String> = candidates.iter().filter(|c| c.as_str() != target).collect();
+    let decoys: Vec<String> = others
+        .choose_multiple(rng, decoy_count)
+        .map(|decoy| decoy.to_string())
+        .collect();
+
+    let mut components: Vec<String> = std::iter::once(target)
+        .chain(decoys.iter().map(String::as_str))
+        .flat_map(|character| leaves_of(character, decompositions))
+        // The answer itself must never be handed out as a component
+        .filter(|component| component != target)
+        .collect();
+    components.shuffle(rng);
+
+    let solution = match solve(&components, target, components_to_chars, decompositions) {
+        Solution::Solved(steps) => Some(steps),
+        Solution::Unsolvable | Solution::TooComplex => None,
+    };
+
+    Puzzle {
+        id,
+        target: target.to_string(),
+        decoys,
+        components,
+        solution,
+    }
+}
+
+/// Generate the rounds of one HSK level; targets are distinct until the level runs out
+pub fn generate_level(
+    level: u8,
+    level_words: &[String],
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    solvability: &SolvabilityReport,
+    config: &PuzzleConfig,
+) -> PuzzleSet {
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+    rng.set_stream(level as u64);
+
+    let candidates = playable_characters(level_words, decompositions);
+    let mut targets: Vec<String> = candidates
+        .iter()
+        .filter(|character| solvability.is_solvable(character))
+        .cloned()
+        .collect();
+    targets.shuffle(&mut rng);
+    targets.truncate(config.rounds_per_level);
+
+    let puzzles = targets
+        .iter()
+        .enumerate()
+        .map(|(index, target)| {
+            let id = format!("{}-{:04}", level, index + 1);
+            generate_puzzle(
+                &mut rng,
+                id,
+                target,
+                &candidates,
+                decompositions,
+                components_to_chars,
+                config.decoys,
+            )
+        })
+        .collect();
+
+    PuzzleSet {
+        level,
+        seed: config.seed,
+        decoy_count: config.decoys,
+        puzzles,
+    }
+}
+
+/// Generate every level of `hsk_words`, sorted by level
+pub fn generate_puzzles(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    solvability: &SolvabilityReport,
+    config: &PuzzleConfig,
+) -> Vec<PuzzleSet> {
+    let mut levels: Vec<u8> = hsk_words.keys().copied().collect();
+    levels.sort_unstable();
+    levels
+        .into_iter()
+        .map(|level| {
+            let words = &hsk_words[&level];
+            generate_level(level, words, decompositions, components_to_chars, solvability, config)
+        })
+        .collect()
+}
+
+/// Save each level to puzzles_<level>.json
+pub fn save_puzzles(puzzle_sets: &[PuzzleSet], output_dir: &str) -> Result<()> {
+    fs::create_dir_all(output_dir)?;
+
+    for puzzle_set in puzzle_sets {
+        let path = format!("{}/puzzles_{}.json", output_dir, puzzle_set.level);
+        let mut file = File::create(&path)?;
+        let json = serde_json::to_string_pretty(puzzle_set)?;
+        file.write_all(json.as_bytes())?;
+        println!("  ✅ Saved {} HSK {} puzzles to {}", puzzle_set.puzzles.len(), puzzle_set.level, path);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data_builder::{build_components_to_chars, decompositions_from};
+    use crate::solver::verify_hsk_characters;
+
+    fn decompositions() -> HashMap<String, CharacterDecomposition> {
+        decompositions_from(&[
+            ("明", "⿰日月"),
+            ("林", "⿰木木"),
+            ("好", "⿰女子"),
+            ("休", "⿰亻木"),
+            ("森", "⿱木林"),
+            ("器", "⿱⿰口口⿱犬⿰口口"),
+        ])
+    }
+
+    fn words() -> Vec<String> {
+        ["明", "林", "好", "休", "森", "器", "日", "明天"].iter().map(|w| w.to_string()).collect()
+    }
+
+    /// generate_level with solvability worked out from the test IDS data
+    fn generate_level(
+        level: u8,
+        words: &[String],
+        decompositions: &HashMap<String, CharacterDecomposition>,
+        config: &PuzzleConfig,
+    ) -> PuzzleSet {
+        let components_to_chars = build_components_to_chars(decompositions);
+        let hsk_words = HashMap::from([(level, words.to_vec())]);
+        let solvability = verify_hsk_characters(&hsk_words, &components_to_chars, decompositions);
+        super::generate_level(level, words, decompositions, &components_to_chars, &solvability, config)
+    }
+
+    #[test]
+    fn test_same_seed_gives_same_puzzles() {
+        let config = PuzzleConfig { seed: 7, decoys: 2, rounds_per_level: 10 };
+        let first = generate_level(1, &words(), &decompositions(), &config);
+        let second = generate_level(1, &words(), &decompositions(), &config);
+        assert_eq!(first, second);
+
+        let reseeded = PuzzleConfig { seed: 8, ..config };
+        let third = generate_level(1, &words(), &decompositions(), &reseeded);
+        assert_ne!(
+            first.puzzles.iter().map(|p| &p.target).collect::<Vec<_>>(),
+            third.puzzles.iter().map(|p| &p.target).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_rounds_use_distinct_playable_targets() {
+        let config = PuzzleConfig { seed: 1, decoys: 2, rounds_per_level: 10 };
+        let set = generate_level(1, &words(), &decompositions(), &config);
+
+        // 日 has no decomposition, 明天 is not a single character and 器 cannot be built
+        let mut targets: Vec<&str> = set.puzzles.iter().map(|p| p.target.as_str()).collect();
+        targets.sort_unstable();
+        assert_eq!(targets, vec!["休", "好", "明", "林", "森"]);
+        assert_eq!(set.puzzles[0].id, "1-0001");
+    }
+
+    #[test]
+    fn test_components_cover_target_and_decoys() {
+        let config = PuzzleConfig { seed: 3, decoys: 1, rounds_per_level: 10 };
+        let set = generate_level(2, &words(), &decompositions(), &config);
+        let decompositions = decompositions();
+
+        for puzzle in &set.puzzles {
+            assert_eq!(puzzle.decoys.len(), 1);
+            assert!(!puzzle.decoys.contains(&puzzle.target));
+
+            let mut expected: Vec<String> = [&puzzle.target, &puzzle.decoys[0]]
+                .iter()
+                .flat_map(|c| leaves_of(c, &decompositions))
+                .filter(|c| c != &puzzle.target)
+                .collect();
+            let mut components = puzzle.components.clone();
+            expected.sort_unstable();
+            components.sort_unstable();
+            assert_eq!(components, expected);
+            assert_eq!(puzzle.solution.as_ref().unwrap().last().unwrap().output, puzzle.target);
+        }
+    }
+
+    #[test]
+    fn test_repeated_leaves_are_kept() {
+        let leaves = leaves_of("森", &decompositions());
+        assert_eq!(leaves, vec!["木", "木", "木"]);
+    }
+
+    #[test]
+    fn test_levels_use_independent_streams() {
+        let config = PuzzleConfig { seed: 5, decoys: 2, rounds_per_level: 10 };
+        let hsk_words = HashMap::from([(1, words()), (2, words())]);
+        let decompositions = decompositions();
+        let components_to_chars = build_components_to_chars(&decompositions);
+        let solvability = verify_hsk_characters(&hsk_words, &components_to_chars, &decompositions);
+        let sets = generate_puzzles(&hsk_words, &decompositions, &components_to_chars, &solvability, &config);
+
+        assert_eq!(sets.iter().map(|s| s.level).collect::<Vec<_>>(), vec![1, 2]);
+        // Generating level 2 on its own gives the same rounds as generating every level
+        assert_eq!(sets[1], generate_level(2, &words(), &decompositions, &config));
+    }
+}
@@ -0,0 +1,150 @@
+// radicals.rs - Kangxi radicals and radical variant folding
+//
+// The 214 radicals of the Kangxi dictionary, as unified ideographs (U+4E00 block and
+// friends) rather than the Kangxi Radicals block (U+2F00–U+2FD5) compatibility forms,
+// since that is how they appear in IDS data.
+//
+// IDS data writes the same radical several ways: as a positional form (氵 for 水, 亻 for 人),
+// as a CJK Radicals Supplement code point (⺡, ⺅) or as a Kangxi Radicals block code point.
+// Folding maps all of these to the Kangxi radical they are a form of, whatever script the
+// variant belongs to (讠 → 言, ⻋ → 車, 攵 → 攴, ⻘ → 靑), so that component keys and the
+// component palette treat them as equal. Folding is only ever applied to compare
+// components; decompositions keep the glyph the IDS used, which is what cards display.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// The 214 Kangxi radicals in radical-number order (一 is 1, 龠 is 214)
+pub const KANGXI_RADICALS: [char; 214] = [
+    '一', '丨', '丶', '丿', '乙', '亅', '二', '亠', '人', '儿', '入', '八', '冂', '冖', '冫', '几', '凵', '刀', '力', '勹',
+    '匕', '匚', '匸', '十', '卜', '卩', '厂', '厶', '又', '口', '囗', '土', '士', '夂', '夊', '夕', '大', '女', '子', '宀',
+    '寸', '小', '尢', '尸', '屮', '山', '巛', '工', '己', '巾', '干', '幺', '广', '廴', '廾', '弋', '弓', '彐', '彡', '彳',
+    '心', '戈', '戶', '手', '支', '攴', '文', '斗', '斤', '方', '无', '日', '曰', '月', '木', '欠', '止', '歹', '殳', '毋',
+    '比', '毛', '氏', '气', '水', '火', '爪', '父', '爻', '爿', '片', '牙', '牛', '犬', '玄', '玉', '瓜', '瓦', '甘', '生',
+    '用', '田', '疋', '疒', '癶', '白', '皮', '皿', '目', '矛', '矢', '石', '示', '禸', '禾', '穴', '立', '竹', '米', '糸',
+    '缶', '网', '羊', '羽', '老', '而', '耒', '耳', '聿', '肉', '臣', '自', '至', '臼', '舌', '舛', '舟', '艮', '色', '艸',
+    '虍', '虫', '血', '行', '衣', '襾', '見', '角', '言', '谷', '豆', '豕', '豸', '貝', '赤', '走', '足', '身', '車', '辛',
+    '辰', '辵', '邑', '酉', '釆', '里', '金', '長', '門', '阜', '隶', '隹', '雨', '靑', '非', '面', '革', '韋', '韭', '音',
+    '頁', '風', '飛', '食', '首', '香', '馬', '骨', '高', '髟', '鬥', '鬯', '鬲', '鬼', '魚', '鳥', '鹵', '鹿', '麥', '麻',
+    '黃', '黍', '黑', '黹', '黽', '鼎', '鼓', '鼠', '鼻', '齊', '齒', '龍', '龜', '龠',
+];
+
+/// Radical variants and the Kangxi radical each one folds to, sorted by variant code point
+///
+/// Kangxi Radicals block code points (U+2F00–U+2FD5) are not listed; they fold to
+/// KANGXI_RADICALS. The only targets that are not Kangxi radicals are CHARACTER_FORMS.
+const RADICAL_VARIANTS: &[(char, char)] = &[
+    // CJK Radicals Supplement
+    ('⺁', '厂'), ('⺅', '人'), ('⺆', '冂'), ('⺊', '卜'), ('⺌', '小'), ('⺍', '小'), ('⺐', '尢'),
+    ('⺒', '巳'), ('⺓', '幺'), ('⺖', '心'), ('⺗', '心'), ('⺘', '手'), ('⺙', '攴'), ('⺜', '日'),
+    ('⺝', '月'), ('⺟', '母'), ('⺠', '民'), ('⺡', '水'), ('⺢', '水'), ('⺣', '火'), ('⺤', '爪'),
+    ('⺥', '爪'), ('⺦', '爿'), ('⺧', '牛'), ('⺨', '犬'), ('⺩', '王'), ('⺫', '网'), ('⺬', '示'),
+    ('⺭', '示'), ('⺮', '竹'), ('⺯', '糸'), ('⺰', '糸'), ('⺲', '网'), ('⺶', '羊'), ('⺷', '羊'),
+    ('⺹', '老'), ('⺻', '聿'), ('⺼', '肉'), ('⺽', '臼'), ('⺾', '艸'), ('⺿', '艸'), ('⻀', '艸'),
+    ('⻁', '虎'), ('⻂', '衣'), ('⻃', '襾'), ('⻄', '西'), ('⻅', '見'), ('⻆', '角'), ('⻈', '言'),
+    ('⻉', '貝'), ('⻋', '車'), ('⻌', '辵'), ('⻍', '辵'), ('⻎', '辵'), ('⻏', '阝'), ('⻐', '金'),
+    ('⻑', '長'), ('⻒', '長'), ('⻓', '長'), ('⻔', '門'), ('⻖', '阝'), ('⻗', '雨'), ('⻘', '靑'),
+    ('⻙', '韋'), ('⻚', '頁'), ('⻛', '風'), ('⻜', '飛'), ('⻝', '食'), ('⻟', '食'), ('⻠', '食'),
+    ('⻢', '馬'), ('⻣', '骨'), ('⻤', '鬼'), ('⻥', '魚'), ('⻦', '鳥'), ('⻧', '鹵'), ('⻨', '麥'),
+    ('⻩', '黃'), ('⻪', '黽'), ('⻫', '齊'), ('⻬', '齊'), ('⻭', '齒'), ('⻮', '齒'), ('⻯', '龍'),
+    ('⻰', '龍'), ('⻱', '龜'), ('⻲', '龜'), ('⻳', '龜'),
+    // Positional forms among the unified ideographs
+    ('丬', '爿'), ('亻', '人'), ('刂', '刀'), ('忄', '心'), ('扌', '手'), ('攵', '攴'), ('氵', '水'),
+    ('氺', '水'), ('灬', '火'), ('爫', '爪'), ('牜', '牛'), ('犭', '犬'), ('礻', '示'), ('糹', '糸'),
+    ('纟', '糸'), ('罒', '网'), ('耂', '老'), ('艹', '艸'), ('衤', '衣'), ('覀', '襾'), ('讠', '言'),
+    ('辶', '辵'), ('钅', '金'), ('飠', '食'), ('饣', '食'),
+    // Astral positional forms
+    ('𠆢', '人'), ('𥫗', '竹'), ('𧾷', '足'),
+];
+
+/// Fold targets that are not Kangxi radicals
+///
+/// These Supplement forms stand for a character of their own rather than a form of their
+/// radical (⺩ is 王, not 玉; ⺟ is 母, not 毋), and the IDS writes that character with its
+/// unified code point, so they fold to it. ⻏ and ⻖ both fold to 阝, the one glyph the IDS
+/// uses for the 邑 and 阜 sides alike.
+#[cfg(test)]
+const CHARACTER_FORMS: [char; 7] = ['巳', '母', '民', '王', '虎', '西', '阝'];
+
+/// The canonical form of a radical variant, or `c` itself
+pub fn fold_radical(c: char) -> char {
+    if ('\u{2F00}'..='\u{2FD5}').contains(&c) {
+        return KANGXI_RADICALS[c as usize - 0x2F00];
+    }
+    RADICAL_VARIANTS
+        .binary_search_by_key(&c, |&(variant, _)| variant)
+        .map_or(c, |index| RADICAL_VARIANTS[index].1)
+}
+
+/// The canonical form of a component; entity references are returned unchanged
+pub fn fold_component(component: &str) -> Cow<'_, str> {
+    let mut chars = component.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if fold_radical(c) != c => Cow::Owned(fold_radical(c).to_string()),
+        _ => Cow::Borrowed(component),
+    }
+}
+
+/// Every variant → canonical form pair, for clients that build component keys
+pub fn radical_folding() -> BTreeMap<String, String> {
+    let kangxi_block = ('\u{2F00}'..='\u{2FD5}').zip(KANGXI_RADICALS);
+    RADICAL_VARIANTS
+        .iter()
+        .copied()
+        .chain(kangxi_block)
+        .map(|(variant, canonical)| (variant.to_string(), canonical.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radicals_are_unified_ideographs() {
+        assert_eq!(KANGXI_RADICALS[0], '一');
+        assert_eq!(KANGXI_RADICALS[84], '水'); // radical 85
+        assert!(KANGXI_RADICALS.contains(&'木'));
+        assert!(!KANGXI_RADICALS.contains(&'\u{2F00}')); // ⼀ KANGXI RADICAL ONE
+    }
+
+    #[test]
+    fn test_variant_table_is_sorted_and_folds_in_one_step() {
+        assert!(RADICAL_VARIANTS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for &(variant, canonical) in RADICAL_VARIANTS {
+            assert_ne!(variant, canonical);
+            assert_eq!(fold_radical(canonical), canonical, "{} folds twice", variant);
+        }
+    }
+
+    #[test]
+    fn test_variants_fold_to_kangxi_radicals() {
+        for &(variant, canonical) in RADICAL_VARIANTS {
+            assert!(
+                KANGXI_RADICALS.contains(&canonical) || CHARACTER_FORMS.contains(&canonical),
+                "{} folds to {}, which is neither a Kangxi radical nor a listed character form",
+                variant,
+                canonical
+            );
+        }
+        // Simplified and traditional forms fold alike
+        assert_eq!(fold_radical('讠'), '言');
+        assert_eq!(fold_radical('⻋'), '車');
+        assert_eq!(fold_radical('⻘'), '靑');
+        assert_eq!(fold_radical('攵'), '攴');
+    }
+
+    #[test]
+    fn test_equivalent_forms_fold_together() {
+        assert_eq!(fold_radical('氵'), '水');
+        assert_eq!(fold_radical('⺡'), '水');
+        assert_eq!(fold_radical('\u{2F54}'), '水'); // ⽔ KANGXI RADICAL WATER
+        assert_eq!(fold_radical('亻'), '人');
+        assert_eq!(fold_radical('扌'), '手');
+        assert_eq!(fold_radical('⺍'), fold_radical('⺌'));
+        assert_eq!(fold_radical('木'), '木');
+        assert_eq!(fold_component("氵"), "水");
+        assert_eq!(fold_component("&CDP-8B7A;"), "&CDP-8B7A;");
+        assert_eq!(radical_folding()["⻌"], "辵");
+    }
+}
@@ -0,0 +1,294 @@
+// script.rs - Simplified / Traditional script selection for game data
+//
+// The dictionary stores every word in both scripts (`simp` and `trad`), and items can be
+// marked as belonging to only one of them. A script decides which form of each HSK word
+// is played, which definitions are kept, and which regional IDS glyphs are preferred.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::chinese_char_types::ChineseCharacter;
+use crate::chinese_types::{ChineseDictionaryElement, Item, SimpTrad};
+
+/// One of the two written forms of Chinese
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Script {
+    Simp,
+    Trad,
+}
+
+impl Script {
+    pub fn name(self) -> &'static str {
+        match self {
+            Script::Simp => "simp",
+            Script::Trad => "trad",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Script::Simp => "Simplified",
+            Script::Trad => "Traditional",
+        }
+    }
+
+    /// The form of a dictionary word written in this script
+    pub fn word_form(self, word: &ChineseDictionaryElement) -> &str {
+        match self {
+            Script::Simp => &word.simp,
+            Script::Trad => &word.trad,
+        }
+    }
+
+    /// Whether a dictionary item applies to words written in this script
+    pub fn includes_item(self, item: &Item) -> bool {
+        match item.simp_trad {
+            None | Some(SimpTrad::Both) => true,
+            Some(SimpTrad::Simp) => self == Script::Simp,
+            Some(SimpTrad::Trad) => self == Script::Trad,
+        }
+    }
+
+    /// IDS region tags whose glyph shapes match this script (mainland G vs Taiwan/Hong Kong)
+    pub fn default_ids_regions(self) -> Vec<char> {
+        match self {
+            Script::Simp => vec!['G'],
+            Script::Trad => vec!['T', 'H'],
+        }
+    }
+}
+
+/// Which scripts to generate game data for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ScriptMode {
+    /// Simplified characters (mainland China)
+    Simp,
+    /// Traditional characters (Taiwan, Hong Kong)
+    #[default]
+    Trad,
+    /// Both, each written to its own subdirectory
+    Both,
+}
+
+impl ScriptMode {
+    pub fn scripts(self) -> Vec<Script> {
+        match self {
+            ScriptMode::Simp => vec![Script::Simp],
+            ScriptMode::Trad => vec![Script::Trad],
+            ScriptMode::Both => vec![Script::Simp, Script::Trad],
+        }
+    }
+
+    /// The script used by commands that inspect a single dataset (decompose, lookup)
+    pub fn primary(self) -> Script {
+        match self {
+            ScriptMode::Simp => Script::Simp,
+            ScriptMode::Trad | ScriptMode::Both => Script::Trad,
+        }
+    }
+
+    /// Where the data for `script` is written: `out` itself for a single script,
+    /// `out/simp` and `out/trad` side by side for both
+    pub fn output_dir(self, out: &str, script: Script) -> String {
+        match self {
+            ScriptMode::Both => format!("{}/{}", out, script.name()),
+            ScriptMode::Simp | ScriptMode::Trad => out.to_string(),
+        }
+    }
+}
+
+/// The other-script forms of one character
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ScriptVariant {
+    /// Simplified forms of a traditional character (發 → 发)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub simp: Vec<String>,
+    /// Traditional forms of a simplified character (发 → 發, 髮)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trad: Vec<String>,
+    /// The standard character this one is a variant of (龢 → 和)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant_of: Option<String>,
+}
+
+/// How a character the player built relates to the one they were asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantMatch {
+    /// Exactly the target character
+    Same,
+    /// The simplified form of a traditional target
+    Simplified,
+    /// The traditional form of a simplified target
+    Traditional,
+    /// A variant of the target, or the character the target is a variant of
+    Variant,
+}
+
+/// Character → other-script counterparts, saved as script_variants.json
+#[derive(Debug, Default, Serialize)]
+#[serde(transparent)]
+pub struct ScriptVariants {
+    variants: BTreeMap<String, ScriptVariant>,
+}
+
+fn push_unique(forms: &mut Vec<String>, form: &str) {
+    if !forms.iter().any(|f| f == form) {
+        forms.push(form.to_string());
+    }
+}
+
+impl ScriptVariants {
+    /// Collect the `simp_variants`, `trad_variants` and `variant_of` links of the character
+    /// dictionary, keeping the characters in `keep`. Links are made symmetric, so 发 gets
+    /// 發 as a traditional form even if only 發 lists 发 as its simplified form.
+    pub fn from_dictionary(chars: &[ChineseCharacter], keep: &HashSet<String>) -> Self {
+        let mut variants: BTreeMap<String, ScriptVariant> = BTreeMap::new();
+
+        for entry in chars {
+            let ch = entry.char.as_str();
+            for simp in entry.simp_variants.iter().flatten().filter(|&s| s != ch) {
+                push_unique(&mut variants.entry(ch.to_string()).or_default().simp, simp);
+                push_unique(&mut variants.entry(simp.clone()).or_default().trad, ch);
+            }
+            for trad in entry.trad_variants.iter().flatten().filter(|&t| t != ch) {
+                push_unique(&mut variants.entry(ch.to_string()).or_default().trad, trad);
+                push_unique(&mut variants.entry(trad.clone()).or_default().simp, ch);
+            }
+            if let Some(variant_of) = entry.variant_of.as_ref().filter(|&v| v != ch) {
+                variants.entry(ch.to_string()).or_default().variant_of = Some(variant_of.clone());
+            }
+        }
+
+        variants.retain(|ch, _| keep.contains(ch));
+        ScriptVariants { variants }
+    }
+
+    pub fn len(&self) -> usize {
+        self.variants.len()
+    }
+
+    pub fn get(&self, ch: &str) -> Option<&ScriptVariant> {
+        self.variants.get(ch)
+    }
+
+    /// The forms of `ch` written in `script`: its counterparts, or `ch` itself if it has none
+    pub fn forms_in<'a>(&'a self, ch: &'a str, script: Script) -> Vec<&'a str> {
+        let counterparts = self.get(ch).map(|variant| match script {
+            Script::Simp => &variant.simp,
+            Script::Trad => &variant.trad,
+        });
+        match counterparts {
+            Some(forms) if !forms.is_empty() => forms.iter().map(String::as_str).collect(),
+            _ => vec![ch],
+        }
+    }
+
+    /// Whether `built` is an acceptable answer for `target`, and in which form
+    pub fn match_build(&self, target: &str, built: &str) -> Option<VariantMatch> {
+        if target == built {
+            return Some(VariantMatch::Same);
+        }
+        let target_variant = self.get(target);
+        let variant_of = |ch: &str| self.get(ch).and_then(|v| v.variant_of.as_deref());
+        if target_variant.is_some_and(|v| v.simp.iter().any(|s| s == built)) {
+            Some(VariantMatch::Simplified)
+        } else if target_variant.is_some_and(|v| v.trad.iter().any(|t| t == built)) {
+            Some(VariantMatch::Traditional)
+        } else if variant_of(target) == Some(built) || variant_of(built) == Some(target) {
+            Some(VariantMatch::Variant)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn character(ch: &str, simp: &[&str], trad: &[&str], variant_of: Option<&str>) -> ChineseCharacter {
+        serde_json::from_value(serde_json::json!({
+            "_id": ch,
+            "char": ch,
+            "codepoint": "",
+            "strokeCount": 0,
+            "sources": [],
+            "simpVariants": simp,
+            "tradVariants": trad,
+            "variantOf": variant_of,
+        }))
+        .unwrap()
+    }
+
+    fn keep(chars: &[&str]) -> HashSet<String> {
+        chars.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_variant_links_are_symmetric() {
+        let chars = vec![
+            character("發", &["发"], &[], None),
+            character("髮", &["发"], &[], None),
+            character("发", &[], &["發"], None),
+        ];
+        let variants = ScriptVariants::from_dictionary(&chars, &keep(&["发", "發", "髮"]));
+
+        assert_eq!(variants.get("发").unwrap().trad, vec!["發", "髮"]);
+        assert_eq!(variants.forms_in("髮", Script::Simp), vec!["发"]);
+        assert_eq!(variants.forms_in("发", Script::Simp), vec!["发"]);
+    }
+
+    #[test]
+    fn test_match_build_reports_the_form_built() {
+        let chars = vec![
+            character("學", &["学"], &[], None),
+            character("龢", &[], &[], Some("和")),
+        ];
+        let variants = ScriptVariants::from_dictionary(&chars, &keep(&["学", "學", "和", "龢"]));
+
+        assert_eq!(variants.match_build("學", "學"), Some(VariantMatch::Same));
+        assert_eq!(variants.match_build("學", "学"), Some(VariantMatch::Simplified));
+        assert_eq!(variants.match_build("学", "學"), Some(VariantMatch::Traditional));
+        assert_eq!(variants.match_build("和", "龢"), Some(VariantMatch::Variant));
+        assert_eq!(variants.match_build("學", "字"), None);
+    }
+
+    #[test]
+    fn test_only_kept_characters_are_exported() {
+        let chars = vec![character("學", &["学"], &[], None)];
+        let variants = ScriptVariants::from_dictionary(&chars, &keep(&["學"]));
+        assert_eq!(variants.len(), 1);
+        assert!(variants.get("学").is_none());
+    }
+
+    fn item(simp_trad: Option<SimpTrad>) -> Item {
+        Item {
+            source: None,
+            pinyin: None,
+            simp_trad,
+            definitions: None,
+            tang: None,
+            variant_refs: None,
+        }
+    }
+
+    #[test]
+    fn test_items_are_filtered_by_script() {
+        assert!(Script::Simp.includes_item(&item(None)));
+        assert!(Script::Simp.includes_item(&item(Some(SimpTrad::Both))));
+        assert!(Script::Simp.includes_item(&item(Some(SimpTrad::Simp))));
+        assert!(!Script::Simp.includes_item(&item(Some(SimpTrad::Trad))));
+        assert!(!Script::Trad.includes_item(&item(Some(SimpTrad::Simp))));
+    }
+
+    #[test]
+    fn test_both_mode_writes_scripts_side_by_side() {
+        assert_eq!(ScriptMode::Both.scripts(), vec![Script::Simp, Script::Trad]);
+        assert_eq!(ScriptMode::Both.output_dir("game_data", Script::Simp), "game_data/simp");
+        assert_eq!(ScriptMode::Trad.output_dir("game_data", Script::Trad), "game_data");
+        assert_eq!(ScriptMode::Both.primary(), Script::Trad);
+    }
+}
@@ -0,0 +1,283 @@
+// solver.rs - Prove that a round can be won
+//
+// The client combines two or three cards at a time: the selected cards form a component key
+// (see component_key.rs) and any character listed under that key in components_to_chars
+// replaces them. The solver runs a breadth-first search over card multisets with the same
+// rule, so the first sequence that produces the target is a shortest one.
+//
+// Only characters inside the target's decomposition can ever be part of a winning sequence,
+// so every other card (decoys) and every other combination result is ignored.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::component_key::component_key;
+use crate::game_data_builder::{leaves_of, CharacterDecomposition};
+
+/// The client combines at most this many cards in one step
+pub const MAX_CARDS_PER_STEP: usize = 3;
+
+/// Search states explored before giving up on a target
+pub const MAX_STATES: usize = 200_000;
+
+/// Combining `inputs` into `output`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildStep {
+    pub inputs: Vec<String>,
+    pub output: String,
+}
+
+/// Outcome of a search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Solution {
+    /// Shortest sequence of combinations that produces the target
+    Solved(Vec<BuildStep>),
+    /// No sequence of combinations produces the target
+    Unsolvable,
+    /// The search reached MAX_STATES without an answer
+    TooComplex,
+}
+
+/// Cards in hand, as component → count
+type Hand = BTreeMap<String, usize>;
+
+/// The target and everything it decomposes into, at any depth
+fn relevant_characters(
+    target: &str,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> HashSet<String> {
+    let mut relevant = HashSet::new();
+    let mut pending = vec![target.to_string()];
+    while let Some(character) = pending.pop() {
+        if !relevant.insert(character.clone()) {
+            continue;
+        }
+        if let Some(decomposition) = decompositions.get(&character) {
+            pending.extend(decomposition.components.iter().cloned());
+        }
+    }
+    relevant
+}
+
+/// Every multiset of 2..=MAX_CARDS_PER_STEP cards that can be taken from `hand`
+fn card_selections(hand: &Hand) -> Vec<Vec<&str>> {
+    fn extend<'a>(
+        cards: &[(&'a str, usize)],
+        start: usize,
+        current: &mut Vec<&'a str>,
+        selections: &mut Vec<Vec<&'a str>>,
+    ) {
+        if current.len() >= 2 {
+            selections.push(current.clone());
+        }
+        if current.len() == MAX_CARDS_PER_STEP {
+            return;
+        }
+        for (index, &(card, count)) in cards.iter().enumerate().skip(start) {
+            let used = current.iter().filter(|&&c| c == card).count();
+            if used < count {
+                current.push(card);
+                extend(cards, index, current, selections);
+                current.pop();
+            }
+        }
+    }
+
+    let cards: Vec<(&str, usize)> = hand.iter().map(|(card, &count)| (card.as_str(), count)).collect();
+    let mut selections = Vec::new();
+    extend(&cards, 0, &mut Vec::new(), &mut selections);
+    selections
+}
+
+/// Find the shortest sequence of combinations that turns `cards` into a hand holding `target`
+pub fn solve(
+    cards: &[String],
+    target: &str,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> Solution {
+    let relevant = relevant_characters(target, decompositions);
+    let mut start = Hand::new();
+    for card in cards.iter().filter(|card| relevant.contains(card.as_str())) {
+        *start.entry(card.clone()).or_default() += 1;
+    }
+    if start.contains_key(target) {
+        return Solution::Solved(Vec::new());
+    }
+
+    // Each explored hand remembers the hand and step it was reached from
+    let mut parents: HashMap<Hand, Option<(Hand, BuildStep)>> = HashMap::from([(start.clone(), None)]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(hand) = queue.pop_front() {
+        for selection in card_selections(&hand) {
+            let Some(outputs) = components_to_chars.get(&component_key(&selection)) else {
+                continue;
+            };
+            for output in outputs.iter().filter(|output| relevant.contains(output.as_str())) {
+                let mut next = hand.clone();
+                for &card in &selection {
+                    let count = next.get_mut(card).expect("selection is taken from the hand");
+                    *count -= 1;
+                    if *count == 0 {
+                        next.remove(card);
+                    }
+                }
+                *next.entry(output.clone()).or_default() += 1;
+                if parents.contains_key(&next) {
+                    continue;
+                }
+
+                let step = BuildStep {
+                    inputs: selection.iter().map(|card| card.to_string()).collect(),
+                    output: output.clone(),
+                };
+                let solved = output == target;
+                parents.insert(next.clone(), Some((hand.clone(), step)));
+                if solved {
+                    return Solution::Solved(steps_to(&next, &parents));
+                }
+                if parents.len() >= MAX_STATES {
+                    return Solution::TooComplex;
+                }
+                queue.push_back(next);
+            }
+        }
+    }
+
+    Solution::Unsolvable
+}
+
+/// Walk the parent links back from `hand` to the starting hand
+fn steps_to(hand: &Hand, parents: &HashMap<Hand, Option<(Hand, BuildStep)>>) -> Vec<BuildStep> {
+    let mut steps = Vec::new();
+    let mut current = hand;
+    while let Some(Some((previous, step))) = parents.get(current) {
+        steps.push(step.clone());
+        current = previous;
+    }
+    steps.reverse();
+    steps
+}
+
+/// Which HSK characters can be built from their own leaf components, saved as solvability.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SolvabilityReport {
+    /// Character → number of combinations in its shortest build sequence
+    pub solvable: BTreeMap<String, usize>,
+    /// Characters that cannot be built with the allowed combinations
+    pub unsolvable: Vec<String>,
+    /// Characters whose search gave up after MAX_STATES hands
+    pub too_complex: Vec<String>,
+}
+
+impl SolvabilityReport {
+    /// Whether `character` was proven buildable
+    pub fn is_solvable(&self, character: &str) -> bool {
+        self.solvable.contains_key(character)
+    }
+}
+
+/// Single characters of a level that decompose into something, in dictionary order
+pub fn playable_characters(
+    level_words: &[String],
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> Vec<String> {
+    level_words
+        .iter()
+        .filter(|word| word.chars().count() == 1)
+        .filter(|word| {
+            decompositions
+                .get(word.as_str())
+                .is_some_and(|d| !d.components.is_empty())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Check every decomposable single-character HSK word
+pub fn verify_hsk_characters(
+    hsk_words: &HashMap<u8, Vec<String>>,
+    components_to_chars: &HashMap<String, Vec<String>>,
+    decompositions: &HashMap<String, CharacterDecomposition>,
+) -> SolvabilityReport {
+    let characters: HashSet<String> = hsk_words
+        .values()
+        .flat_map(|words| playable_characters(words, decompositions))
+        .collect();
+
+    let mut report = SolvabilityReport::default();
+    for character in characters {
+        let cards = leaves_of(&character, decompositions);
+        match solve(&cards, &character, components_to_chars, decompositions) {
+            Solution::Solved(steps) => {
+                report.solvable.insert(character, steps.len());
+            }
+            Solution::Unsolvable => report.unsolvable.push(character),
+            Solution::TooComplex => report.too_complex.push(character),
+        }
+    }
+    report.unsolvable.sort_unstable();
+    report.too_complex.sort_unstable();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_data_builder::{build_components_to_chars, decompositions_from};
+
+    type Data = (HashMap<String, Vec<String>>, HashMap<String, CharacterDecomposition>);
+
+    fn data(entries: &[(&str, &str)]) -> Data {
+        let decompositions = decompositions_from(entries);
+        (build_components_to_chars(&decompositions), decompositions)
+    }
+
+    fn cards(cards: &[&str]) -> Vec<String> {
+        cards.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_shortest_sequence_goes_through_intermediates() {
+        // 謝 = 言 + 射, 射 = 身 + 寸
+        let (c2c, decompositions) = data(&[("謝", "⿰言射"), ("射", "⿰身寸")]);
+        let solution = solve(&cards(&["寸", "言", "身", "日"]), "謝", &c2c, &decompositions);
+
+        // The leaf key 寸|言|身 builds 謝 in a single step
+        assert_eq!(
+            solution,
+            Solution::Solved(vec![BuildStep { inputs: cards(&["寸", "言", "身"]), output: "謝".to_string() }])
+        );
+    }
+
+    #[test]
+    fn test_four_leaves_need_two_steps() {
+        // 樹 = 木 + 尌, 尌 = 壴 + 寸, 壴 = 士 + 豆: four leaves, never three at once
+        let (c2c, decompositions) = data(&[("樹", "⿰木尌"), ("尌", "⿰壴寸"), ("壴", "⿱士豆")]);
+        let Solution::Solved(steps) = solve(&cards(&["木", "士", "豆", "寸"]), "樹", &c2c, &decompositions) else {
+            panic!("樹 should be solvable");
+        };
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps.last().unwrap().output, "樹");
+    }
+
+    #[test]
+    fn test_missing_cards_are_unsolvable() {
+        let (c2c, decompositions) = data(&[("明", "⿰日月")]);
+        assert_eq!(solve(&cards(&["日", "日"]), "明", &c2c, &decompositions), Solution::Unsolvable);
+        assert_eq!(solve(&cards(&["明"]), "明", &c2c, &decompositions), Solution::Solved(Vec::new()));
+    }
+
+    #[test]
+    fn test_report_flags_characters_that_need_more_than_three_cards() {
+        // 器 = four 口 around 犬, all at the top level: no pair or triple makes progress
+        let (c2c, decompositions) = data(&[("明", "⿰日月"), ("器", "⿱⿰口口⿱犬⿰口口")]);
+        let hsk_words = HashMap::from([(1, cards(&["明", "器"]))]);
+        let report = verify_hsk_characters(&hsk_words, &c2c, &decompositions);
+
+        assert_eq!(report.solvable.get("明"), Some(&1));
+        assert_eq!(report.unsolvable, vec!["器"]);
+        assert!(!report.is_solvable("器"));
+    }
+}
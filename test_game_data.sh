@@ -20,12 +20,12 @@ echo ""
 
 echo "Test 1: Character 明 (bright)"
 echo "  Decomposition: $(jq -c '.["明"]' game_data/char_to_decomposition.json)"
-echo "  Reverse lookup (日月): $(jq -c '.["日月"]' game_data/components_to_chars.json)"
+echo "  Reverse lookup (日月): $(jq -c '.["日|月"]' game_data/components_to_chars.json)"
 
 echo ""
 echo "Test 2: Character 好 (good)"
 echo "  Decomposition: $(jq -c '.["好"]' game_data/char_to_decomposition.json)"
-echo "  Reverse lookup (女子): $(jq -c '.["女子"]' game_data/components_to_chars.json)"
+echo "  Reverse lookup (女子): $(jq -c '.["女|子"]' game_data/components_to_chars.json)"
 
 echo ""
 echo "Test 3: HSK 1 Sample Words (first 20)"
@@ -28,6 +28,7 @@ import {
 	checkAnswer,
 	nextRound
 } from '../lib/gameLogic.js';
+import { radicalFoldingFromSpec } from '../lib/componentKey.js';
 
 const __filename = fileURLToPath(import.meta.url);
 const __dirname = path.dirname(__filename);
@@ -59,12 +60,16 @@ function loadGameData(): GameData {
 	const hskWords = JSON.parse(
 		fs.readFileSync(path.join(STATIC_DIR, 'game_data/hsk_words.json'), 'utf-8')
 	);
+	const keySpec = JSON.parse(
+		fs.readFileSync(path.join(STATIC_DIR, 'game_data/components_key_spec.json'), 'utf-8')
+	);
 
 	return {
 		charToDecomposition: charToDecomp,
 		componentsToChars,
 		allowedComponents,
-		hskWords
+		hskWords,
+		radicalFolding: radicalFoldingFromSpec(keySpec)
 	};
 }
 
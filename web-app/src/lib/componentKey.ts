@@ -0,0 +1,84 @@
+/**
+ * Canonical keys for components_to_chars.json.
+ *
+ * Must match src/component_key.rs (also described in game_data/components_key_spec.json):
+ * radical variants folded (氵 → 水), components sorted by Unicode code point, duplicates
+ * kept, joined with "|".
+ * 明 → "日|月", 林 → "木|木", 敝 → "&CDP-8B7A;|攴", 海 → "每|水"
+ *
+ * The folding table is `radical_folding` in components_key_spec.json.
+ */
+export const KEY_SEPARATOR = '|';
+
+/**
+ * Version of the key format these functions build; must equal `version` in
+ * components_key_spec.json (KEY_FORMAT_VERSION in src/component_key.rs)
+ */
+export const KEY_FORMAT_VERSION = 3;
+
+/**
+ * The radical folding table of a components_key_spec.json, checked against the key format
+ * this client builds. Throws if the spec is missing or of another version: keys built
+ * without the right folding would silently stop matching components_to_chars.json.
+ */
+export function radicalFoldingFromSpec(spec: unknown): Record<string, string> {
+	const { version, radical_folding } = (spec ?? {}) as { version?: number; radical_folding?: Record<string, string> };
+	if (version !== KEY_FORMAT_VERSION) {
+		throw new Error(
+			`components_key_spec.json has key format version ${version ?? 'none'}, expected ${KEY_FORMAT_VERSION}; regenerate the game data`
+		);
+	}
+	if (!radical_folding) {
+		throw new Error('components_key_spec.json has no radical_folding table');
+	}
+	return radical_folding;
+}
+
+function compareCodePoints(a: string, b: string): number {
+	// Default string comparison uses UTF-16 code units, which puts astral characters
+	// such as 𠂉 before U+E000-U+FFFF; compare whole code points instead
+	const aPoints = Array.from(a, (c) => c.codePointAt(0)!);
+	const bPoints = Array.from(b, (c) => c.codePointAt(0)!);
+	for (let i = 0; i < Math.min(aPoints.length, bPoints.length); i++) {
+		if (aPoints[i] !== bPoints[i]) return aPoints[i] - bPoints[i];
+	}
+	return aPoints.length - bPoints.length;
+}
+
+/**
+ * The canonical form of a radical variant (氵 → 水), or the component itself
+ */
+export function foldComponent(component: string, radicalFolding: Record<string, string>): string {
+	return radicalFolding[component] ?? component;
+}
+
+export function componentKey(components: string[], radicalFolding: Record<string, string>): string {
+	return components
+		.map((c) => foldComponent(c, radicalFolding))
+		.sort(compareCodePoints)
+		.join(KEY_SEPARATOR);
+}
+
+/**
+ * Key format written before the separator and folding were introduced: raw components
+ * sorted by code point and joined with no separator (明 → "日月", 海 → "每氵").
+ * Only used to read game data that has not been regenerated yet.
+ */
+export function legacyComponentKey(components: string[]): string {
+	return [...components].sort(compareCodePoints).join('');
+}
+
+/**
+ * Characters the components can form: looked up by the canonical key, falling back to the
+ * legacy key so game data built before the separator was introduced keeps working
+ */
+export function lookupComponents(
+	componentsToChars: Record<string, string[]>,
+	components: string[],
+	radicalFolding: Record<string, string>
+): string[] | undefined {
+	return (
+		componentsToChars[componentKey(components, radicalFolding)] ??
+		componentsToChars[legacyComponentKey(components)]
+	);
+}
@@ -1,4 +1,6 @@
-import type { GameData, Card, GameState, Hint } from './types';
+import type { GameData, Card, GameState, Hint, ScriptVariant } from './types';
+import { componentKey, foldComponent, lookupComponents, radicalFoldingFromSpec } from './componentKey';
+import { matchBuild } from './scriptVariants';
 
 let gameData: GameData | null = null;
 
@@ -18,7 +20,7 @@ export async function loadGameData(): Promise<GameData> {
 	// }
 
 	console.log('📥 Loading game data...');
-	const [charToDecomp, componentsToChars, allowedComponents, hskWords, wordGlosses, charGlosses] = await Promise.all([
+	const [charToDecomp, componentsToChars, allowedComponents, hskWords, wordGlosses, charGlosses, scriptVariants, keySpec, charPinyin, wordPinyin] = await Promise.all([
 		fetch('/game_data/char_to_decomposition.json').then((r) => r.json()),
 		fetch('/game_data/components_to_chars.json').then((r) => r.json()),
 		fetch('/game_data/allowed_components.json').then((r) => r.json()),
@@ -30,6 +32,23 @@ export async function loadGameData(): Promise<GameData> {
 		fetch('/game_data/char_glosses.json').then((r) => r.json()).catch((e) => {
 			console.warn('⚠️ Failed to load char glosses:', e);
 			return {};
+		}),
+		fetch('/game_data/script_variants.json').then((r) => r.json()).catch((e) => {
+			console.warn('⚠️ Failed to load script variants:', e);
+			return {};
+		}),
+		// Required: without its folding table no component key would match
+		fetch('/game_data/components_key_spec.json').then((r) => {
+			if (!r.ok) throw new Error(`Failed to load components_key_spec.json (${r.status})`);
+			return r.json();
+		}),
+		fetch('/game_data/char_pinyin.json').then((r) => r.json()).catch((e) => {
+			console.warn('⚠️ Failed to load character pinyin:', e);
+			return {};
+		}),
+		fetch('/game_data/word_pinyin.json').then((r) => r.json()).catch((e) => {
+			console.warn('⚠️ Failed to load word pinyin:', e);
+			return {};
 		})
 	]);
 
@@ -41,12 +60,28 @@ export async function loadGameData(): Promise<GameData> {
 		allowedComponents,
 		hskWords,
 		wordGlosses,
-		charGlosses
+		charGlosses,
+		scriptVariants,
+		charPinyin,
+		wordPinyin,
+		radicalFolding: radicalFoldingFromSpec(keySpec)
 	};
 
 	return gameData;
 }
 
+/**
+ * Uniform Fisher-Yates shuffle (sorting with a random comparator favours the original order)
+ */
+export function shuffle<T>(items: T[]): T[] {
+	const shuffled = [...items];
+	for (let i = shuffled.length - 1; i > 0; i--) {
+		const j = Math.floor(Math.random() * (i + 1));
+		[shuffled[i], shuffled[j]] = [shuffled[j], shuffled[i]];
+	}
+	return shuffled;
+}
+
 export function getHSKLevel(totalRoundsCompleted: number, roundsPerLevel: number): number {
 	const level = Math.floor(totalRoundsCompleted / roundsPerLevel) + 1;
 	return Math.min(level, 7); // Cap at HSK 7
@@ -94,6 +129,11 @@ export function decomposeToLeaves(
 		return [character];
 	}
 
+	// The builder stores leaves at the chosen granularity (--stop-at, --max-depth)
+	if (path.length === 0 && decomp.leaves) {
+		return decomp.leaves;
+	}
+
 	const leaves: string[] = [];
 	const newPath = [...path, character];
 
@@ -160,7 +200,7 @@ export function getComponentsWithDecoys(
 	});
 
 	// Shuffle and pick 2 decoy characters
-	const shuffled = [...availableDecoyChars].sort(() => Math.random() - 0.5);
+	const shuffled = shuffle(availableDecoyChars);
 	const selectedDecoys = shuffled.slice(0, 2);
 
 	// Add components from decoy characters (including duplicates)
@@ -178,7 +218,7 @@ export function getComponentsWithDecoys(
 	});
 
 	// Randomize the order of components
-	const shuffledComponents = filteredComponents.sort(() => Math.random() - 0.5);
+	const shuffledComponents = shuffle(filteredComponents);
 
 	return {
 		components: shuffledComponents,
@@ -222,8 +262,7 @@ function findValidNextSteps(
 		for (let j = i + 1; j < cards.length; j++) {
 			const comp1 = cards[i].character;
 			const comp2 = cards[j].character;
-			const sortedKey = [comp1, comp2].sort().join('');
-			const possibleResults = componentsToCharsMap[sortedKey] || [];
+			const possibleResults = lookupComponents(componentsToCharsMap, [comp1, comp2], data.radicalFolding) || [];
 
 			// Check each possible result
 			for (const result of possibleResults) {
@@ -257,8 +296,8 @@ function findValidNextSteps(
 				const comp1 = cards[i].character;
 				const comp2 = cards[j].character;
 				const comp3 = cards[k].character;
-				const sortedKey = [comp1, comp2, comp3].sort().join('');
-				const possibleResults = componentsToCharsMap[sortedKey] || [];
+				const possibleResults =
+					lookupComponents(componentsToCharsMap, [comp1, comp2, comp3], data.radicalFolding) || [];
 
 				// Check each possible result
 				for (const result of possibleResults) {
@@ -398,14 +437,17 @@ function hasEnoughComponents(selectedChars: string[], requiredComponents: string
 	console.log(`      hasEnoughComponents: required [${requiredComponents.join(', ')}]`);
 
 	// Count occurrences of each component in selected leaf components
+	// Radical variants count as the same component (氵 satisfies 水)
 	const selectedCounts = new Map<string, number>();
-	for (const char of selectedLeafComponents) {
+	for (const leaf of selectedLeafComponents) {
+		const char = foldComponent(leaf, data.radicalFolding);
 		selectedCounts.set(char, (selectedCounts.get(char) || 0) + 1);
 	}
 
 	// Count occurrences of each component in required components
 	const requiredCounts = new Map<string, number>();
-	for (const char of requiredComponents) {
+	for (const leaf of requiredComponents) {
+		const char = foldComponent(leaf, data.radicalFolding);
 		requiredCounts.set(char, (requiredCounts.get(char) || 0) + 1);
 	}
 
@@ -439,8 +481,8 @@ export function findPossibleCombinations(
 	const checkSubset = (chars: string[]) => {
 		if (chars.length === 0) return;
 
-		const sortedKey = chars.slice().sort().join('');
-		const matches = data.componentsToChars[sortedKey];
+		const sortedKey = componentKey(chars, data.radicalFolding);
+		const matches = lookupComponents(data.componentsToChars, chars, data.radicalFolding);
 
 		if (matches) {
 			console.log(`  Checking subset [${chars.join(', ')}] → key: "${sortedKey}" → ${matches.length} potential matches`);
@@ -515,7 +557,11 @@ export function decomposeCard(card: Card, availableCards: Card[], data: GameData
 	return [...remaining, ...newCards];
 }
 
-export function checkAnswer(availableCards: Card[], targetWord: string): boolean {
+export function checkAnswer(
+	availableCards: Card[],
+	targetWord: string,
+	scriptVariants: Record<string, ScriptVariant> = {}
+): boolean {
 	// Get all characters from available cards
 	const availableChars = availableCards.map((c) => c.character);
 
@@ -527,7 +573,8 @@ export function checkAnswer(availableCards: Card[], targetWord: string): boolean
 	const remainingChars = [...availableChars];
 
 	for (const targetChar of targetChars) {
-		const index = remainingChars.indexOf(targetChar);
+		// The other script's form of a character (学 for 學) counts as well
+		const index = remainingChars.findIndex((c) => matchBuild(targetChar, c, scriptVariants) !== null);
 		if (index === -1) {
 			// Target character not found in available cards
 			return false;
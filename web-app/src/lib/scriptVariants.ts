@@ -0,0 +1,21 @@
+import type { ScriptVariant, VariantMatch } from './types';
+
+/**
+ * How a character the player built relates to the one they were asked for.
+ * Mirrors ScriptVariants::match_build in src/script.rs; returns null if it is not a form of the target.
+ */
+export function matchBuild(
+	target: string,
+	built: string,
+	scriptVariants: Record<string, ScriptVariant>
+): VariantMatch | null {
+	if (target === built) return 'same';
+
+	const targetVariant = scriptVariants[target];
+	if (targetVariant?.simp?.includes(built)) return 'simplified';
+	if (targetVariant?.trad?.includes(built)) return 'traditional';
+	if (targetVariant?.variant_of === built || scriptVariants[built]?.variant_of === target) {
+		return 'variant';
+	}
+	return null;
+}
@@ -1,9 +1,33 @@
+export interface DecompositionNode {
+	component?: string; // Absent for nested sequences without a name of their own
+	operator?: string; // IDS operator joining the children, absent for leaves
+	position?: 'left' | 'middle' | 'right' | 'top' | 'bottom' | 'outer' | 'inner' | 'base' | 'overlay';
+	leaf_count: number;
+	children?: DecompositionNode[];
+}
+
 export interface CharacterDecomposition {
 	character: string;
 	ids: string;
 	components: string[];
+	tree?: DecompositionNode; // Full recursive decomposition, absent if the IDS is malformed
+	leaves?: string[]; // Leaf components at the granularity the data was built with
+}
+
+export interface ScriptVariant {
+	simp?: string[]; // Simplified forms of a traditional character (發 → 发)
+	trad?: string[]; // Traditional forms of a simplified character (发 → 發, 髮)
+	variant_of?: string; // Standard character this one is a variant of (龢 → 和)
 }
 
+export interface Reading {
+	marked: string; // With tone marks, for display ("míng bai")
+	numbered: string; // With tone numbers, for matching typed input ("ming2 bai5")
+	share?: number; // Share of uses with this reading, where the dictionary counts them
+}
+
+export type VariantMatch = 'same' | 'simplified' | 'traditional' | 'variant';
+
 export interface GameData {
 	charToDecomposition: Record<string, CharacterDecomposition>;
 	componentsToChars: Record<string, string[]>;
@@ -11,6 +35,10 @@ export interface GameData {
 	hskWords: Record<string, string[]>;
 	wordGlosses?: Record<string, string[]>; // Array of definitions for words
 	charGlosses?: Record<string, string[]>; // Array of definitions for characters (includes top words)
+	scriptVariants?: Record<string, ScriptVariant>; // Simplified ↔ traditional counterparts of HSK characters
+	charPinyin?: Record<string, Reading[]>; // Readings of HSK characters, most frequent first
+	wordPinyin?: Record<string, Reading[]>; // Readings of HSK words
+	radicalFolding: Record<string, string>; // Radical variant → canonical form used in component keys (氵 → 水)
 }
 
 export interface Card {